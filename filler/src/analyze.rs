@@ -0,0 +1,134 @@
+use std::{fmt::Write, path::Path, time::Instant};
+
+use filler::{snapshot, Choice, Position, ScoreBreakdown, State};
+
+/// Runs the normal move selection on a single position and reports the
+/// move the bot would play, every legal move, the board with that move
+/// placed, and timings.
+/// `state` carries the weights, opening book and player override to
+/// analyse with.
+pub fn run(path: &Path, state: State) -> Result<String, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+
+    let start = Instant::now();
+    let state =
+        snapshot::parse_into(state, &text).map_err(|err| format!("{}: {err}", path.display()))?;
+    let parsed = start.elapsed();
+    if state.anfield.width == 0 {
+        return Err(format!("{}: no board found", path.display()));
+    }
+
+    let start = Instant::now();
+    let mut book = None;
+    let mut positions = Vec::new();
+    let (x, y) = state.choose_move(|choice| match choice {
+        Choice::Book(origin) => book = Some(origin),
+        Choice::Ranked(ranked) => positions = ranked.to_vec(),
+    });
+    let evaluated = start.elapsed();
+    // The book move skips the ranking, which is still worth seeing.
+    if book.is_some() {
+        positions = state.ranked_positions();
+    }
+    let chosen = match book {
+        Some(_) => Some(Position {
+            x,
+            y,
+            robot_idx: state.robot.id,
+            piece: state.current_piece.clone(),
+        }),
+        None => positions.first().map(|p| p.0.clone()),
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "player {} ({}), piece {}x{}, evaluator {:?}",
+        state.robot.id,
+        state.robot.characters.iter().collect::<String>(),
+        state.current_piece.width,
+        state.current_piece.height,
+        state.evaluator()
+    );
+    let _ = writeln!(
+        out,
+        "parse {:.3} ms, evaluation {:.3} ms, {} legal moves\n",
+        parsed.as_secs_f64() * 1000.0,
+        evaluated.as_secs_f64() * 1000.0,
+        positions.len()
+    );
+    if chosen.is_some() {
+        let source = match book {
+            Some(_) => "from the opening book",
+            None => "ranked first",
+        };
+        let _ = writeln!(out, "move {x} {y} {source}\n");
+    }
+    ranking(&mut out, &positions);
+    out.push('\n');
+    board(&mut out, &state, chosen.as_ref());
+    Ok(out)
+}
+
+fn ranking(out: &mut String, positions: &[(Position, ScoreBreakdown)]) {
+    let Some((_, first)) = positions.first() else {
+        out.push_str("no legal move\n");
+        return;
+    };
+    let _ = write!(out, "{:>4} {:>4} {:>4} {:>9}", "rank", "x", "y", "total");
+    for t in &first.terms {
+        let _ = write!(out, "  {:>22}", t.name);
+    }
+    out.push('\n');
+    for (rank, (p, breakdown)) in positions.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{:>4} {:>4} {:>4} {:>9.2}",
+            rank + 1,
+            p.x,
+            p.y,
+            breakdown.total
+        );
+        for t in &breakdown.terms {
+            let _ = write!(
+                out,
+                "  {:>22}",
+                format!("{:.2} x {} = {:.2}", t.value, t.weight, t.contribution())
+            );
+        }
+        out.push('\n');
+    }
+}
+
+/// The board in referee layout, with the cells of `chosen` drawn as `*`.
+fn board(out: &mut String, state: &State, chosen: Option<&Position>) {
+    let anfield = &state.anfield;
+    let _ = writeln!(
+        out,
+        "    {}",
+        (0..anfield.width)
+            .map(|i| (i % 10).to_string())
+            .collect::<String>()
+    );
+    for y in 0..anfield.height {
+        let _ = write!(out, "{y:03} ");
+        for x in 0..anfield.width {
+            let covered = chosen.is_some_and(|p| {
+                let (j, i) = (x - p.x, y - p.y);
+                j >= 0
+                    && i >= 0
+                    && j < p.piece.width
+                    && i < p.piece.height
+                    && p.piece.cells[i as usize][j as usize] != '.'
+            });
+            let c = match anfield.occupation.get(&(x, y)) {
+                _ if covered => '*',
+                None | Some(0) => '.',
+                Some(&id) => state.players.symbols(id).map_or('?', |s| s[1]),
+            };
+            out.push(c);
+        }
+        out.push('\n');
+    }
+}
@@ -1,19 +1,55 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     process::{Piece, Robot},
-    Position,
+    Position, ScoreBreakdown, Weights,
 };
 
-#[derive(Debug, Clone, Default)]
+/// Two boards are equal when they have the same size, owners and recent
+/// cells. The frontier and score caches are left out of the comparison.
+#[derive(Debug, Clone)]
 pub struct Anfield {
     pub width: i32,
     pub height: i32,
     pub occupation: HashMap<(i32, i32), i32>,
-    pub opp_occupation: Vec<Cell>,
+    pub opp_occupation: HashSet<Cell>,
+    /// Id of the robot `opp_occupation` was computed for.
+    pub perspective: i32,
+    /// Free neighbours an opponent cell needs beyond this to be in
+    /// `opp_occupation`.
+    pub frontier_min_free: usize,
+    pub scores: HashMap<i32, u32>,
+    /// Cells of each player's last placed piece, drawn with the first
+    /// symbol of the player.
+    pub recent: HashSet<(i32, i32)>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl PartialEq for Anfield {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self.occupation == other.occupation
+            && self.recent == other.recent
+    }
+}
+
+impl Eq for Anfield {}
+
+impl Default for Anfield {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+/// What `Anfield::place` changed, so that `Anfield::undo` can revert it.
+#[derive(Debug, Clone)]
+pub struct Undo {
+    cells: Vec<((i32, i32), Option<i32>)>,
+    frontier_added: Vec<Cell>,
+    frontier_removed: Vec<Cell>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Cell {
     pub x: i32,
     pub y: i32,
@@ -26,33 +62,138 @@ impl Anfield {
             width,
             height,
             occupation: HashMap::new(),
-            opp_occupation: Vec::new(),
+            opp_occupation: HashSet::new(),
+            perspective: 0,
+            frontier_min_free: 2,
+            scores: HashMap::new(),
+            recent: HashSet::new(),
+        }
+    }
+
+    /// Sets the owner of a cell, keeping `scores` in sync. Players without
+    /// cells have no entry in `scores`.
+    pub fn claim(&mut self, coord: (i32, i32), id: i32) {
+        let prev = self.occupation.insert(coord, id).unwrap_or(0);
+        if prev == id {
+            return;
+        }
+        if let Some(s) = self.scores.get_mut(&prev) {
+            *s -= 1;
+            if *s == 0 {
+                self.scores.remove(&prev);
+            }
+        }
+        if id != 0 {
+            *self.scores.entry(id).or_insert(0) += 1;
+        }
+    }
+
+    pub fn score(&self, id: i32) -> u32 {
+        self.scores.get(&id).copied().unwrap_or(0)
+    }
+
+    fn is_frontier(&self, coord: (i32, i32)) -> bool {
+        match self.occupation.get(&coord) {
+            Some(&id) if id != 0 && id != self.perspective => {
+                let cell = Cell::new(coord.0, coord.1, id);
+                let n = cell.get_neightboor(self);
+                n.iter().filter(|c| c.occupied_by == 0).count() > self.frontier_min_free
+            }
+            _ => false,
         }
     }
 
     pub fn update_opp_occupation(&mut self, robot: &Robot) {
+        self.perspective = robot.id;
+        self.frontier_min_free = robot.weights.frontier_min_free.round().max(0.0) as usize;
         self.opp_occupation = self
             .occupation
             .iter()
-            .filter(|&(c, id)| {
-                let cell = Cell::new(c.0, c.1, *id);
-                let n = cell.get_neightboor(&self);
-                let c = n.iter().filter(|c| c.occupied_by == 0).count();
-                c > 2 && (*id != robot.id && *id != 0)
-            })
+            .filter(|&(&c, _)| self.is_frontier(c))
             .map(|(&(x, y), id)| Cell::new(x, y, *id))
             .collect()
     }
 
+    /// Places `piece` at `origin` for `player` without checking legality.
+    /// Only the piece cells and their neighbours are touched, so a search
+    /// can make and unmake moves on a single board.
+    pub fn place(&mut self, piece: &Piece, origin: (i32, i32), player: i32) -> Undo {
+        let mut undo = Undo {
+            cells: Vec::new(),
+            frontier_added: Vec::new(),
+            frontier_removed: Vec::new(),
+        };
+        let mut previous = HashMap::new();
+        for i in 0..piece.height {
+            for j in 0..piece.width {
+                if piece.cells[i as usize][j as usize] != '.' {
+                    let coord = (origin.0 + j, origin.1 + i);
+                    let prev = self.occupation.get(&coord).copied();
+                    previous.insert(coord, prev.unwrap_or(0));
+                    undo.cells.push((coord, prev));
+                    self.claim(coord, player);
+                }
+            }
+        }
+
+        let mut touched = HashSet::new();
+        for &((x, y), _) in &undo.cells {
+            for di in -1..=1 {
+                for dj in -1..=1 {
+                    touched.insert((x + dj, y + di));
+                }
+            }
+        }
+        for coord in touched {
+            let Some(&id) = self.occupation.get(&coord) else {
+                continue;
+            };
+            let old = Cell::new(coord.0, coord.1, *previous.get(&coord).unwrap_or(&id));
+            let was = self.opp_occupation.contains(&old);
+            let is = self.is_frontier(coord);
+            let new = Cell::new(coord.0, coord.1, id);
+            if was && (!is || old != new) {
+                self.opp_occupation.remove(&old);
+                undo.frontier_removed.push(old);
+            }
+            if is && !self.opp_occupation.contains(&new) {
+                self.opp_occupation.insert(new.clone());
+                undo.frontier_added.push(new);
+            }
+        }
+        undo
+    }
+
+    /// Reverts a placement returned by `place`. Undos must be applied in
+    /// reverse order.
+    pub fn undo(&mut self, undo: Undo) {
+        for c in &undo.frontier_added {
+            self.opp_occupation.remove(c);
+        }
+        self.opp_occupation.extend(undo.frontier_removed);
+        for (coord, prev) in undo.cells.into_iter().rev() {
+            match prev {
+                Some(id) => self.claim(coord, id),
+                None => {
+                    self.claim(coord, 0);
+                    self.occupation.remove(&coord);
+                }
+            }
+        }
+    }
+
+    /// Whether `piece` at `coord` stays on the board, covers exactly one cell
+    /// of `robot` and no cell of anyone else.
     pub fn can_place(&self, coord: (i32, i32), robot: &Robot, piece: &Piece) -> bool {
         let mut touch = 0;
         for i in 0..piece.height {
             for j in 0..piece.width {
                 if piece.cells[i as usize][j as usize] != '.' {
-                    if coord.0 + j >= self.width || &coord.1 + i >= self.height {
+                    let (x, y) = (coord.0 + j, coord.1 + i);
+                    if x < 0 || y < 0 || x >= self.width || y >= self.height {
                         return false;
                     }
-                    if let Some(c) = self.occupation.get(&(&coord.0 + j, &coord.1 + i)) {
+                    if let Some(c) = self.occupation.get(&(coord.0 + j, coord.1 + i)) {
                         if *c == robot.id {
                             touch += 1
                         } else if *c != 0 {
@@ -65,21 +206,36 @@ impl Anfield {
         touch == 1
     }
 
-    pub fn potential_positions(&self, piece: &Piece, robot: &Robot) -> HashMap<Position, f32> {
+    /// Every origin at which some cell of `piece` can be on the board,
+    /// row by row. Padded pieces can have origins above or left of it.
+    pub fn origins(&self, piece: &Piece) -> impl Iterator<Item = (i32, i32)> {
+        let (width, height) = (self.width, self.height);
+        let left = 1 - piece.width;
+        (1 - piece.height..height).flat_map(move |y| (left..width).map(move |x| (x, y)))
+    }
+
+    pub fn has_legal_move(&self, piece: &Piece, robot: &Robot) -> bool {
+        self.origins(piece)
+            .any(|coord| self.can_place(coord, robot, piece))
+    }
+
+    pub fn potential_positions(
+        &self,
+        piece: &Piece,
+        robot: &Robot,
+    ) -> HashMap<Position, ScoreBreakdown> {
         let mut positions = HashMap::new();
-        (0..self.height).into_iter().for_each(|i| {
-            (0..self.width).into_iter().for_each(|j| {
-                if self.can_place((j, i), robot, piece) {
-                    let p = Position {
-                        x: j,
-                        y: i,
-                        robot_idx: robot.id,
-                        piece: piece.clone(),
-                    };
-                    positions.insert(p.clone(), p.score(self, robot));
-                }
-            })
-        });
+        for (x, y) in self.origins(piece) {
+            if self.can_place((x, y), robot, piece) {
+                let p = Position {
+                    x,
+                    y,
+                    robot_idx: robot.id,
+                    piece: piece.clone(),
+                };
+                positions.insert(p.clone(), p.score(self, robot));
+            }
+        }
         positions
     }
 }
@@ -93,20 +249,19 @@ impl Cell {
         }
     }
 
-    pub fn blocking_potential(&self, anfield: &Anfield) -> i32 {
+    pub fn blocking_potential(&self, anfield: &Anfield, weights: &Weights) -> i32 {
         let mut blocking_score = 0;
         for cell in self.get_neightboor(anfield) {
             if cell.occupied_by != self.occupied_by && cell.occupied_by != 0 {
-                blocking_score += 20
-                    * cell
-                        .get_neightboor(anfield)
-                        .iter()
-                        .filter(|c| c.occupied_by == 0)
-                        .count()
-                    / 8;
+                let free = cell
+                    .get_neightboor(anfield)
+                    .iter()
+                    .filter(|c| c.occupied_by == 0)
+                    .count();
+                blocking_score += (weights.blocking_neighbour * free as f32 / 8.0) as i32;
             }
         }
-        (blocking_score / 8) as i32
+        blocking_score / 8
     }
 
     pub fn get_neightboor(&self, anfield: &Anfield) -> Vec<Cell> {
@@ -0,0 +1,189 @@
+//! Builds an opening book from self-play on one map. Each game perturbs
+//! both sides' weights, so that they play different openings. For every
+//! early move, the origin that won most often from the same position
+//! becomes the book move, if it won more than half of at least
+//! `--min-games` games.
+
+use std::{collections::HashMap, env, fs, path::PathBuf, process};
+
+use filler::{
+    book::{BookKey, OpeningBook},
+    selfplay::{self, Rng},
+    snapshot, Weights,
+};
+
+const USAGE: &str = "usage: book (--map <position> | --starts x,y:x,y [--size WxH]) \
+                     [--games N] [--depth N] [--noise F] [--min-games N] [--seed N] \
+                     [--weights <path>] [--out <path>]";
+
+struct Options {
+    games: u32,
+    size: (i32, i32),
+    /// Start cells of p1 and p2.
+    starts: Option<[(i32, i32); 2]>,
+    depth: u32,
+    /// Relative spread of the weight perturbations.
+    noise: f32,
+    min_games: u32,
+    seed: u64,
+    weights: Weights,
+    out: PathBuf,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        games: 200,
+        size: (20, 15),
+        starts: None,
+        depth: 4,
+        noise: 0.2,
+        min_games: 3,
+        seed: 1,
+        weights: Weights::default(),
+        out: PathBuf::from("book.txt"),
+    };
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(String::as_str)
+                .ok_or_else(|| format!("`{arg}` expects a value\n{USAGE}"))
+        };
+        let number = |v: &str| v.parse().map_err(|_| format!("invalid number `{v}`"));
+        match arg.as_str() {
+            "--games" => options.games = number(value()?)?,
+            "--depth" => options.depth = number(value()?)?,
+            "--min-games" => options.min_games = number(value()?)?,
+            "--noise" => {
+                let v = value()?;
+                options.noise = v.parse().map_err(|_| format!("invalid noise `{v}`"))?;
+            }
+            "--starts" => {
+                let v = value()?;
+                let invalid = || format!("invalid starts `{v}`, expected x,y:x,y");
+                let cell = |c: &str| -> Result<(i32, i32), String> {
+                    let (x, y) = c.split_once(',').ok_or_else(invalid)?;
+                    Ok((
+                        x.trim().parse().map_err(|_| invalid())?,
+                        y.trim().parse().map_err(|_| invalid())?,
+                    ))
+                };
+                let (a, b) = v.split_once(':').ok_or_else(invalid)?;
+                options.starts = Some([cell(a)?, cell(b)?]);
+            }
+            "--map" => {
+                let path = PathBuf::from(value()?);
+                let state = snapshot::load(&path)
+                    .map_err(|err| format!("could not load {}: {err}", path.display()))?;
+                let theirs = state
+                    .opponents
+                    .first()
+                    .ok_or_else(|| format!("{}: no second player on the map", path.display()))?;
+                options.size = (state.anfield.width, state.anfield.height);
+                options.starts = Some([state.robot.starting_point, theirs.starting_point]);
+            }
+            "--seed" => options.seed = value()?.parse().map_err(|_| "invalid seed")?,
+            "--size" => {
+                let v = value()?;
+                let (w, h) = v
+                    .split_once('x')
+                    .ok_or_else(|| format!("invalid size `{v}`"))?;
+                options.size = (number(w)? as i32, number(h)? as i32);
+            }
+            "--weights" => {
+                let path = PathBuf::from(value()?);
+                options.weights = Weights::load(&path)
+                    .map_err(|err| format!("could not load {}: {err}", path.display()))?;
+            }
+            "--out" => options.out = PathBuf::from(value()?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            other => return Err(format!("unknown argument `{other}`\n{USAGE}")),
+        }
+    }
+    if options.starts.is_none() {
+        return Err(format!("`--map` or `--starts` is required\n{USAGE}"));
+    }
+    Ok(options)
+}
+
+/// Games an origin was played in from one position, and the points it
+/// scored there, a draw counting half.
+#[derive(Debug, Default)]
+struct Record {
+    games: u32,
+    points: f32,
+}
+
+impl Record {
+    fn rate(&self) -> f32 {
+        self.points / self.games.max(1) as f32
+    }
+}
+
+/// `weights` with each one scaled by a random factor in `1 ± noise`.
+fn perturb(weights: &Weights, noise: f32, rng: &mut Rng) -> Weights {
+    let v: Vec<f32> = weights
+        .to_vec()
+        .iter()
+        .map(|w| w * (1.0 + noise * (rng.below(2001) as f32 / 1000.0 - 1.0)))
+        .collect();
+    Weights::from_slice(&v)
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(2)
+    });
+
+    let (w, h) = options.size;
+    let starts = options.starts.unwrap_or_default();
+    let mut records: HashMap<BookKey, HashMap<(i32, i32), Record>> = HashMap::new();
+    let mut rng = Rng::new(options.seed);
+
+    for _ in 0..options.games {
+        let players = [
+            perturb(&options.weights, options.noise, &mut rng),
+            perturb(&options.weights, options.noise, &mut rng),
+        ];
+        let outcome = selfplay::play(w, h, starts, players, &mut rng);
+        for m in outcome.moves.iter().filter(|m| m.turn <= options.depth) {
+            let points = match outcome.winner {
+                Some(winner) if winner == m.player => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            let key = BookKey::new(options.size, starts[m.player], m.turn, &m.piece);
+            let record = records.entry(key).or_default().entry(m.origin).or_default();
+            record.games += 1;
+            record.points += points;
+        }
+    }
+
+    let mut book = OpeningBook::default();
+    for (key, origins) in records {
+        let best = origins
+            .iter()
+            .filter(|(_, r)| r.games >= options.min_games)
+            .max_by(|a, b| {
+                (a.1.rate(), a.1.games)
+                    .partial_cmp(&(b.1.rate(), b.1.games))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then((b.0 .1, b.0 .0).cmp(&(a.0 .1, a.0 .0)))
+            });
+        if let Some((&origin, _)) = best.filter(|(_, r)| r.rate() > 0.5) {
+            book.entries.insert(key, origin);
+        }
+    }
+
+    if let Err(err) = fs::write(&options.out, book.to_string()) {
+        eprintln!("could not write {}: {err}", options.out.display());
+        process::exit(1)
+    }
+    eprintln!(
+        "{} book entries written to {}",
+        book.entries.len(),
+        options.out.display()
+    );
+}
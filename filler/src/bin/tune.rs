@@ -0,0 +1,151 @@
+//! SPSA tuning of the evaluation weights through local self-play.
+//!
+//! Each iteration plays the weights perturbed in a random direction against
+//! the weights perturbed in the opposite direction, estimates the gradient
+//! from the win rate and steps along it. Every candidate is then measured
+//! against the starting weights and the best one is written out.
+
+use std::{env, fs, path::PathBuf, process};
+
+use filler::{
+    selfplay::{self, Rng},
+    Weights,
+};
+
+const USAGE: &str = "usage: tune [--iterations N] [--games N] [--size WxH] [--seed N] \
+                     [--start <weights>] [--out <weights>]";
+
+struct Options {
+    iterations: u32,
+    games: u32,
+    size: (i32, i32),
+    seed: u64,
+    start: Weights,
+    out: PathBuf,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        iterations: 50,
+        games: 20,
+        size: (20, 15),
+        seed: 1,
+        start: Weights::default(),
+        out: PathBuf::from("weights.txt"),
+    };
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(String::as_str)
+                .ok_or_else(|| format!("`{arg}` expects a value\n{USAGE}"))
+        };
+        let number = |v: &str| v.parse().map_err(|_| format!("invalid number `{v}`"));
+        match arg.as_str() {
+            "--iterations" => options.iterations = number(value()?)?,
+            "--games" => options.games = number(value()?)?,
+            "--seed" => options.seed = value()?.parse().map_err(|_| "invalid seed")?,
+            "--size" => {
+                let v = value()?;
+                let (w, h) = v
+                    .split_once('x')
+                    .ok_or_else(|| format!("invalid size `{v}`"))?;
+                options.size = (number(w)? as i32, number(h)? as i32);
+            }
+            "--start" => {
+                let path = PathBuf::from(value()?);
+                options.start = Weights::load(&path)
+                    .map_err(|err| format!("could not load {}: {err}", path.display()))?;
+            }
+            "--out" => options.out = PathBuf::from(value()?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            other => return Err(format!("unknown argument `{other}`\n{USAGE}")),
+        }
+    }
+    Ok(options)
+}
+
+/// Fraction of `games` won by `a` against `b`, draws counting half. Both
+/// sides play each seed once as player 1 and once as player 2.
+fn win_rate(a: &Weights, b: &Weights, options: &Options, seed: u64) -> f32 {
+    let (w, h) = options.size;
+    let starts = [(w / 5, h / 5), (w - 1 - w / 5, h - 1 - h / 5)];
+    let mut points = 0.0;
+    for g in 0..options.games {
+        let swap = g % 2 == 1;
+        let mut rng = Rng::new(seed + (g / 2) as u64);
+        let players = if swap { [*b, *a] } else { [*a, *b] };
+        let outcome = selfplay::play(w, h, starts, players, &mut rng);
+        points += match outcome.winner {
+            Some(i) if (i == 0) != swap => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        };
+    }
+    points / options.games.max(1) as f32
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(2)
+    });
+
+    let start = options.start.to_vec();
+    // Perturbations are relative to the size of each starting weight.
+    let scale: Vec<f32> = start.iter().map(|w| w.abs().max(1.0)).collect();
+    let mut theta = start.clone();
+    let mut best = (options.start, 0.5);
+    let mut rng = Rng::new(options.seed);
+
+    for k in 0..options.iterations {
+        let a = 0.2 / (k as f32 + 1.0 + 5.0).powf(0.602);
+        let c = 0.1 / (k as f32 + 1.0).powf(0.101);
+        let delta: Vec<f32> = start
+            .iter()
+            .map(|_| if rng.coin() { 1.0 } else { -1.0 })
+            .collect();
+
+        let shifted = |sign: f32| -> Vec<f32> {
+            theta
+                .iter()
+                .zip(&delta)
+                .zip(&scale)
+                .map(|((t, d), s)| t + sign * c * d * s)
+                .collect()
+        };
+        let plus = Weights::from_slice(&shifted(1.0));
+        let minus = Weights::from_slice(&shifted(-1.0));
+        let seed = rng.next_u64();
+        let rate = win_rate(&plus, &minus, &options, seed);
+
+        // rate - (1 - rate): how much better the `plus` side did.
+        let diff = 2.0 * rate - 1.0;
+        for i in 0..theta.len() {
+            theta[i] += a * diff / (2.0 * c * delta[i]) * scale[i];
+        }
+
+        let candidate = Weights::from_slice(&theta);
+        let score = win_rate(&candidate, &options.start, &options, rng.next_u64());
+        eprintln!(
+            "iteration {}: {:.2} against the start weights ({:?})",
+            k + 1,
+            score,
+            theta
+        );
+        if score > best.1 {
+            best = (candidate, score);
+        }
+    }
+
+    let contents = format!(
+        "# win rate {:.2} against the start weights\n{}",
+        best.1, best.0
+    );
+    if let Err(err) = fs::write(&options.out, contents) {
+        eprintln!("could not write {}: {err}", options.out.display());
+        process::exit(1)
+    }
+    eprintln!("best weights written to {}", options.out.display());
+}
@@ -0,0 +1,116 @@
+//! Opening book: preferred origins for the first moves on a known map.
+//!
+//! One entry per line, whitespace separated:
+//! `width height start_x start_y move piece x y`, where `piece` is the
+//! piece rows joined with `/`. `#` starts a comment. Lines in the older
+//! format, with the opponent start after ours, are still read and their
+//! opponent start ignored.
+
+use std::{collections::HashMap, fmt, fs, io, path::Path};
+
+use crate::{Piece, State};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BookKey {
+    pub size: (i32, i32),
+    /// Our starting cell. The opponent's is left out: when we are p2, it is
+    /// already under p1's first piece on the first board we see.
+    pub start: (i32, i32),
+    /// Our move number, starting at 1.
+    pub turn: u32,
+    pub piece: String,
+}
+
+impl BookKey {
+    pub fn new(size: (i32, i32), start: (i32, i32), turn: u32, piece: &Piece) -> Self {
+        Self {
+            size,
+            start,
+            turn,
+            piece: piece_key(piece),
+        }
+    }
+
+    pub fn from_state(state: &State) -> Self {
+        Self::new(
+            (state.anfield.width, state.anfield.height),
+            state.robot.starting_point,
+            state.turn,
+            &state.current_piece,
+        )
+    }
+}
+
+fn piece_key(piece: &Piece) -> String {
+    piece
+        .cells
+        .iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OpeningBook {
+    pub entries: HashMap<BookKey, (i32, i32)>,
+}
+
+impl OpeningBook {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut book = Self::default();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() == 10 {
+                fields.drain(4..6);
+            }
+            if fields.len() != 8 {
+                return Err(format!("line {}: expected 8 fields", n + 1));
+            }
+            let num = |i: usize| -> Result<i32, String> {
+                fields[i]
+                    .parse()
+                    .map_err(|_| format!("line {}: invalid number `{}`", n + 1, fields[i]))
+            };
+            let key = BookKey {
+                size: (num(0)?, num(1)?),
+                start: (num(2)?, num(3)?),
+                turn: num(4)? as u32,
+                piece: fields[5].to_string(),
+            };
+            book.entries.insert(key, (num(6)?, num(7)?));
+        }
+        Ok(book)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn get(&self, key: &BookKey) -> Option<(i32, i32)> {
+        self.entries.get(key).copied()
+    }
+}
+
+impl fmt::Display for OpeningBook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by(|a, b| {
+            (a.0.size, a.0.start, a.0.turn, &a.0.piece)
+                .cmp(&(b.0.size, b.0.start, b.0.turn, &b.0.piece))
+        });
+        writeln!(f, "# width height start_x start_y move piece x y")?;
+        for (k, (x, y)) in entries {
+            writeln!(
+                f,
+                "{} {} {} {} {} {} {x} {y}",
+                k.size.0, k.size.1, k.start.0, k.start.1, k.turn, k.piece
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use filler::{
+    book::OpeningBook,
+    logger,
+    protocol::{self, Validation},
+    PlayerRegistry, Weights,
+};
+
+const USAGE: &str = "usage: filler [analyze <snapshot>] [-d|--debug] [--log <filter>] \
+                     [--log-file <path>] [--dump-candidates <path>] [--weights <path>] \
+                     [--book <path>] [--player pN] [--symbols a@,s$,...] [--strict | --lenient] \
+                     [--record <path> | --replay <path>]";
+
+#[derive(Debug, Default)]
+pub enum Command {
+    /// Play against the referee on stdin/stdout.
+    #[default]
+    Play,
+    /// Rank the legal moves of a single saved position.
+    Analyze(PathBuf),
+    /// Play a recorded session again and report the moves that changed.
+    Replay(PathBuf),
+}
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub command: Command,
+    pub log: logger::Config,
+    /// Write the ranked candidates of every turn to this file, one JSON
+    /// object per line.
+    pub dump_candidates: Option<PathBuf>,
+    /// Evaluation weights, as written by the `tune` binary.
+    pub weights: Weights,
+    pub book: OpeningBook,
+    /// Our player number, instead of the one from the exec line.
+    pub player: Option<i32>,
+    /// Board symbols of each player, in player order.
+    pub players: Option<PlayerRegistry>,
+    /// What to do with malformed turns. Unless given, live games recover
+    /// from them and `analyze` and `--replay` stop.
+    pub validation: Option<Validation>,
+    /// Log every input line and answer to this file.
+    pub record: Option<PathBuf>,
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.trim() {
+                "-d" | "--debug" => options.log.enable_debug(),
+                "--log" => options.log.apply_filter(value(&mut args, arg)?)?,
+                "--log-file" => options.log.file = Some(PathBuf::from(value(&mut args, arg)?)),
+                "--dump-candidates" => {
+                    options.dump_candidates = Some(PathBuf::from(value(&mut args, arg)?))
+                }
+                "--weights" => {
+                    let path = PathBuf::from(value(&mut args, arg)?);
+                    options.weights = Weights::load(&path)
+                        .map_err(|err| format!("could not load {}: {err}", path.display()))?;
+                }
+                "--book" => {
+                    let path = PathBuf::from(value(&mut args, arg)?);
+                    options.book = OpeningBook::load(&path)
+                        .map_err(|err| format!("could not load {}: {err}", path.display()))?;
+                }
+                "--player" => {
+                    let v = value(&mut args, arg)?;
+                    options.player = Some(
+                        protocol::parse_player(v).ok_or_else(|| format!("invalid player `{v}`"))?,
+                    );
+                }
+                "--strict" => options.validation = Some(Validation::Strict),
+                "--lenient" => options.validation = Some(Validation::Lenient),
+                "--record" => options.record = Some(PathBuf::from(value(&mut args, arg)?)),
+                "--replay" => options.command = Command::Replay(value(&mut args, arg)?.into()),
+                "--symbols" => {
+                    options.players = Some(PlayerRegistry::parse(value(&mut args, arg)?)?)
+                }
+                "analyze" => options.command = Command::Analyze(value(&mut args, arg)?.into()),
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                other => return Err(format!("unknown argument `{other}`\n{USAGE}")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn value<'a>(args: &mut impl Iterator<Item = &'a String>, flag: &str) -> Result<&'a str, String> {
+    args.next()
+        .map(String::as_str)
+        .ok_or_else(|| format!("`{flag}` expects a value\n{USAGE}"))
+}
@@ -0,0 +1,78 @@
+//! The two board formats referees speak.
+//!
+//! Ours sends `Anfield W H:` boards, `a@`/`s$` symbols and `O` piece cells,
+//! and reads moves as `x y`. The classic 42 VM sends `Plateau H W:` boards,
+//! `oO`/`xX` symbols and `*` piece cells, gives every size height first and
+//! reads moves as `y x`.
+
+use std::fmt;
+
+use crate::PlayerRegistry;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    #[default]
+    Anfield,
+    Plateau,
+}
+
+impl Dialect {
+    pub const ALL: [Dialect; 2] = [Dialect::Anfield, Dialect::Plateau];
+
+    /// Dialect of the first board header in `lines`.
+    pub fn detect<S: AsRef<str>>(lines: &[S]) -> Option<Self> {
+        lines.iter().find_map(|l| Self::of_header(l.as_ref()))
+    }
+
+    /// Dialect of a board header line, `None` for any other line.
+    pub fn of_header(line: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|d| line.starts_with(d.keyword()))
+    }
+
+    /// First word of a board header.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Dialect::Anfield => "Anfield",
+            Dialect::Plateau => "Plateau",
+        }
+    }
+
+    /// Width and height from the two numbers of a header, or the two
+    /// numbers of a header from width and height.
+    pub fn size(self, (a, b): (i32, i32)) -> (i32, i32) {
+        match self {
+            Dialect::Anfield => (a, b),
+            Dialect::Plateau => (b, a),
+        }
+    }
+
+    /// Filled cell of a piece, read as `O` whatever the dialect.
+    pub fn piece_cell(self) -> char {
+        match self {
+            Dialect::Anfield => 'O',
+            Dialect::Plateau => '*',
+        }
+    }
+
+    /// Board symbols the referee uses unless told otherwise.
+    pub fn players(self) -> PlayerRegistry {
+        match self {
+            Dialect::Anfield => PlayerRegistry::default(),
+            Dialect::Plateau => PlayerRegistry::new(vec![['o', 'O'], ['x', 'X']]),
+        }
+    }
+
+    /// The line placing a piece with its top left corner at `x`, `y`.
+    pub fn answer(self, x: i32, y: i32) -> String {
+        let (a, b) = self.size((x, y));
+        format!("{a} {b}")
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.keyword().to_lowercase())
+    }
+}
@@ -0,0 +1,201 @@
+//! Rules of the game on their own: whose turn it is, which moves are legal,
+//! when the game is over. No referee text involved.
+
+use std::{cmp::Ordering, fmt};
+
+use crate::{Anfield, Piece, Robot};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Move {
+    /// `piece` placed with its top-left corner at `origin`.
+    Place {
+        player: i32,
+        piece: Piece,
+        origin: (i32, i32),
+    },
+    /// The player could not place its piece and is out of the game.
+    Pass { player: i32 },
+}
+
+impl Move {
+    pub fn player(&self) -> i32 {
+        match self {
+            Move::Place { player, .. } | Move::Pass { player } => *player,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    /// The move is by someone else than `Game::to_move`.
+    NotYourTurn { expected: Option<i32>, got: i32 },
+    /// `Anfield::can_place` refuses the placement.
+    IllegalMove(Move),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::NotYourTurn {
+                expected: Some(p),
+                got,
+            } => write!(f, "player {got} moved during the turn of player {p}"),
+            GameError::NotYourTurn {
+                expected: None,
+                got,
+            } => {
+                write!(f, "player {got} moved after the end of the game")
+            }
+            GameError::IllegalMove(Move::Place { player, origin, .. }) => write!(
+                f,
+                "player {player} cannot place its piece at {} {}",
+                origin.0, origin.1
+            ),
+            GameError::IllegalMove(Move::Pass { player }) => {
+                write!(f, "player {player} cannot pass")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    anfield: Anfield,
+    /// Player ids in turn order.
+    players: Vec<i32>,
+    /// Players that passed, and so won't move again.
+    out: Vec<i32>,
+    /// Index in `players` of the player to move.
+    next: usize,
+}
+
+impl Game {
+    /// A game on `map`, which already holds the starting cells, between
+    /// players `1..=players`. Player 1 moves first.
+    pub fn new(map: Anfield, players: usize) -> Self {
+        Self {
+            anfield: map,
+            players: (1..=players as i32).collect(),
+            out: Vec::new(),
+            next: 0,
+        }
+    }
+
+    pub fn anfield(&self) -> &Anfield {
+        &self.anfield
+    }
+
+    /// For caches such as the opponent frontier. Changing cells through it
+    /// bypasses the rules.
+    pub fn anfield_mut(&mut self) -> &mut Anfield {
+        &mut self.anfield
+    }
+
+    pub fn players(&self) -> &[i32] {
+        &self.players
+    }
+
+    /// Next player still in the game, `None` once it is over.
+    pub fn to_move(&self) -> Option<i32> {
+        (0..self.players.len())
+            .map(|k| self.players[(self.next + k) % self.players.len()])
+            .find(|p| !self.out.contains(p))
+    }
+
+    pub fn is_out(&self, player: i32) -> bool {
+        self.out.contains(&player)
+    }
+
+    /// Every placement of `piece` that `player` may play.
+    pub fn legal_moves(&self, player: i32, piece: &Piece) -> Vec<Move> {
+        let robot = Robot::new(player, ['?', '?']);
+        self.anfield
+            .origins(piece)
+            .filter(|&origin| self.anfield.can_place(origin, &robot, piece))
+            .map(|origin| Move::Place {
+                player,
+                piece: piece.clone(),
+                origin,
+            })
+            .collect()
+    }
+
+    /// Plays `mv` for the player to move. Passing is always allowed and
+    /// takes the player out of the game.
+    pub fn apply(&mut self, mv: Move) -> Result<(), GameError> {
+        let expected = self.to_move();
+        if expected != Some(mv.player()) {
+            return Err(GameError::NotYourTurn {
+                expected,
+                got: mv.player(),
+            });
+        }
+        match &mv {
+            Move::Place {
+                player,
+                piece,
+                origin,
+            } => {
+                let robot = Robot::new(*player, ['?', '?']);
+                if !self.anfield.can_place(*origin, &robot, piece) {
+                    return Err(GameError::IllegalMove(mv));
+                }
+                self.anfield.place(piece, *origin, *player);
+                self.mark_recent(*player, piece, *origin);
+            }
+            Move::Pass { player } => self.out.push(*player),
+        }
+        let i = self
+            .players
+            .iter()
+            .position(|&p| p == mv.player())
+            .unwrap_or(0);
+        self.next = (i + 1) % self.players.len();
+        Ok(())
+    }
+
+    /// Makes the cells of `piece` the only recent ones of `player`.
+    fn mark_recent(&mut self, player: i32, piece: &Piece, origin: (i32, i32)) {
+        let anfield = &mut self.anfield;
+        anfield
+            .recent
+            .retain(|c| anfield.occupation.get(c) != Some(&player));
+        for (y, row) in piece.cells.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                if *c != '.' {
+                    anfield
+                        .recent
+                        .insert((origin.0 + x as i32, origin.1 + y as i32));
+                }
+            }
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        self.to_move().is_none()
+    }
+
+    /// Cells owned by each player, in turn order.
+    pub fn scores(&self) -> Vec<(i32, u32)> {
+        self.players
+            .iter()
+            .map(|&p| (p, self.anfield.score(p)))
+            .collect()
+    }
+
+    /// Player with the most cells, `None` on a tie for first place.
+    pub fn winner(&self) -> Option<i32> {
+        let mut scores = self.scores();
+        scores.sort_by_key(|s| std::cmp::Reverse(s.1));
+        match scores.as_slice() {
+            [(p, _)] => Some(*p),
+            [(p, a), (_, b), ..] => match a.cmp(b) {
+                Ordering::Greater => Some(*p),
+                _ => None,
+            },
+            [] => None,
+        }
+    }
+}
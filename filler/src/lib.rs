@@ -1,19 +1,19 @@
 mod anfield;
+pub mod book;
+pub mod dialect;
+pub mod game;
+pub mod logger;
+mod players;
 mod process;
+pub mod protocol;
+mod score;
+pub mod selfplay;
+pub mod session;
+pub mod snapshot;
+mod weights;
 
 pub use anfield::*;
+pub use players::*;
 pub use process::*;
-
-pub mod flag {
-    pub static mut DEBUG: bool = false;
-}
-
-pub mod logger {
-    use crate::flag;
-
-    pub fn console_log<T: std::fmt::Debug>(value: T) {
-        if unsafe { flag::DEBUG } {
-            println!("{:?}", value);
-        }
-    }
-}
+pub use score::*;
+pub use weights::*;
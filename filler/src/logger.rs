@@ -0,0 +1,179 @@
+//! Leveled logging. Output goes to stderr or to a log file, never to stdout:
+//! stdout is the channel the referee reads our moves from.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "off" => Ok(Level::Off),
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            other => Err(format!("unknown log level `{other}`")),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Off => "OFF",
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub level: Level,
+    /// Per-module levels, matched against `module_path!()` prefixes.
+    pub modules: Vec<(String, Level)>,
+    /// Log to this file instead of stderr.
+    pub file: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            level: Level::Warn,
+            modules: Vec::new(),
+            file: None,
+        }
+    }
+}
+
+impl Config {
+    /// Applies a filter such as `info,filler::process=trace`: a bare level
+    /// sets the default, `module=level` adds a per-module override.
+    pub fn apply_filter(&mut self, spec: &str) -> Result<(), String> {
+        for part in spec.split(',').filter(|p| !p.trim().is_empty()) {
+            match part.split_once('=') {
+                Some((module, level)) => self
+                    .modules
+                    .push((module.trim().to_string(), level.parse()?)),
+                None => self.level = part.parse()?,
+            }
+        }
+        Ok(())
+    }
+
+    /// What `-d` turns on: debug everywhere, plus the per-candidate scoring
+    /// traces of `filler::process`. Filters given afterwards still win.
+    pub fn enable_debug(&mut self) {
+        self.level = Level::Debug;
+        self.modules
+            .push(("filler::process".to_string(), Level::Trace));
+    }
+
+    pub fn level_for(&self, module: &str) -> Level {
+        self.modules
+            .iter()
+            .filter(|(m, _)| module == m || module.starts_with(&format!("{m}::")))
+            .max_by_key(|(m, _)| m.len())
+            .map(|&(_, level)| level)
+            .unwrap_or(self.level)
+    }
+}
+
+struct Logger {
+    config: Config,
+    sink: Mutex<Box<dyn Write + Send>>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Installs the logger. Only the first call has an effect; logging before
+/// `init` uses the default configuration.
+pub fn init(config: Config) -> io::Result<()> {
+    let sink: Box<dyn Write + Send> = match &config.file {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stderr()),
+    };
+    let _ = LOGGER.set(Logger {
+        config,
+        sink: Mutex::new(sink),
+    });
+    Ok(())
+}
+
+fn logger() -> &'static Logger {
+    LOGGER.get_or_init(|| Logger {
+        config: Config::default(),
+        sink: Mutex::new(Box::new(io::stderr())),
+    })
+}
+
+pub fn enabled(module: &str, level: Level) -> bool {
+    level != Level::Off && level <= logger().config.level_for(module)
+}
+
+pub fn log(module: &str, level: Level, args: fmt::Arguments) {
+    if !enabled(module, level) {
+        return;
+    }
+    if let Ok(mut sink) = logger().sink.lock() {
+        let _ = writeln!(sink, "[{level} {module}] {args}");
+    }
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::logger::log(module_path!(), $crate::logger::Level::Error, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::logger::log(module_path!(), $crate::logger::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::logger::log(module_path!(), $crate::logger::Level::Info, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logger::log(module_path!(), $crate::logger::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        $crate::logger::log(module_path!(), $crate::logger::Level::Trace, format_args!($($arg)*))
+    };
+}
@@ -1,64 +1,136 @@
+mod analyze;
+mod cli;
+
 use std::{
     env,
-    io::{self, BufRead},
+    fs::File,
+    io::{self, Write},
+    process,
 };
 
-use filler::{flag, State};
+use cli::{Command, Options};
+use filler::{
+    book_json, candidates_json, info, logger,
+    protocol::{self, Validation},
+    session::{self, Recorder},
+    warn, Choice, State,
+};
 
 fn main() {
-    let mut input_lines = Vec::new();
-    let stdin = io::stdin();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = Options::parse(&args).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(2)
+    });
+    if let Err(err) = logger::init(options.log.clone()) {
+        eprintln!("could not open log file: {err}");
+        process::exit(2)
+    }
+    info!("log level: {}", options.log.level);
+
     let mut state = State::new();
-    let mut rem_line = i32::MAX;
+    state.weights = options.weights;
+    state.book = options.book;
+    state.player_override = options.player;
+    if let Some(players) = options.players.clone() {
+        state.players = players;
+        state.symbols_given = true;
+    }
+    // A live game is better played on from a malformed turn than forfeited;
+    // offline, the mismatch is what we want to hear about.
+    state.validation = options.validation.unwrap_or(match options.command {
+        Command::Play => Validation::Lenient,
+        Command::Analyze(_) | Command::Replay(_) => Validation::Strict,
+    });
 
-    let args: Vec<String> = env::args().collect();
-    if let Some(flag) = args.get(1) {
-        if flag.trim() == "-d" || flag.trim() == "--debug" {
-            unsafe { flag::DEBUG = true };
-            println!("\nMode: DEBUG")
+    if let Command::Analyze(path) = &options.command {
+        match analyze::run(path, state) {
+            Ok(report) => print!("{report}"),
+            Err(err) => {
+                eprintln!("{err}");
+                process::exit(1)
+            }
         }
+        return;
     }
-
-    loop {
-        'read_buffer: for line in stdin.lock().lines() {
-            if let Ok(l) = line {
-                if l.starts_with("Piece") {
-                    let part = l
-                        .trim_matches(|c: char| !c.is_numeric())
-                        .split_once(' ')
-                        .expect("error while spliting");
-
-                    let height: i32 = part.1.parse().expect("error while parsing");
-                    rem_line = height;
-                    input_lines.push(l);
-                    continue;
+    if let Command::Replay(path) = &options.command {
+        match session::replay(path, state) {
+            Ok(replay) => {
+                print!("{replay}");
+                if !replay.changes.is_empty() {
+                    process::exit(1)
                 }
-                input_lines.push(l);
             }
-            rem_line -= 1;
-            if rem_line < 1 {
-                rem_line = i32::MAX;
-                break 'read_buffer;
+            Err(err) => {
+                eprintln!("{err}");
+                process::exit(1)
             }
         }
+        return;
+    }
+    let mut dump = options.dump_candidates.as_ref().map(|path| {
+        File::create(path).unwrap_or_else(|err| {
+            eprintln!("could not open {}: {err}", path.display());
+            process::exit(2)
+        })
+    });
 
-        state.parse(input_lines.clone());
-        input_lines.clear();
-
-        state.anfield.update_opp_occupation(&state.robot);
+    let mut recorder = options.record.as_ref().map(|path| {
+        Recorder::create(path).unwrap_or_else(|err| {
+            eprintln!("could not open {}: {err}", path.display());
+            process::exit(2)
+        })
+    });
 
-        let mut positions: Vec<_> = state
-            .anfield
-            .potential_positions(&state.current_piece, &state.robot)
-            .into_iter()
-            .collect();
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
 
-        positions.sort_by(|a, b| a.1.total_cmp(&b.1));
+    loop {
+        let input_lines = match protocol::read_turn(&mut input) {
+            Ok(Some(lines)) => lines,
+            Ok(None) => break,
+            Err(err) => {
+                eprintln!("could not read turn: {err}");
+                process::exit(1)
+            }
+        };
+        if let Some(recorder) = recorder.as_mut() {
+            for line in &input_lines {
+                if let Err(err) = recorder.input(line) {
+                    warn!("could not record input: {err}");
+                }
+            }
+        }
+        let first = !state.started;
+        if let Err(err) = state.parse(input_lines) {
+            eprintln!("{err}");
+            process::exit(1)
+        }
+        if let Some(recorder) = recorder.as_mut().filter(|_| first) {
+            if let Err(err) = recorder.identity(state.robot.id, state.dialect) {
+                warn!("could not record identity: {err}");
+            }
+        }
 
-        if let Some(p) = positions.last() {
-            println!("{} {}", p.0.x, p.0.y);
-        } else {
-            println!("0 0");
+        let (x, y) = state.choose_move(|choice| {
+            if let Some(file) = dump.as_mut() {
+                let json = match choice {
+                    Choice::Book(origin) => book_json(state.turn, origin),
+                    Choice::Ranked(positions) => {
+                        candidates_json(state.turn, state.evaluator(), positions)
+                    }
+                };
+                if let Err(err) = writeln!(file, "{json}") {
+                    warn!("could not dump candidates: {err}");
+                }
+            }
+        });
+        let answer = state.dialect.answer(x, y);
+        println!("{answer}");
+        if let Some(recorder) = recorder.as_mut() {
+            if let Err(err) = recorder.output(&answer) {
+                warn!("could not record answer: {err}");
+            }
         }
     }
 }
@@ -0,0 +1,91 @@
+//! Board symbols of each player. Player `n` owns the `n`-th symbol pair:
+//! the first symbol marks its last placed piece, the second the rest of its
+//! cells.
+
+use std::fmt;
+
+use crate::Robot;
+
+pub const DEFAULT_SYMBOLS: [[char; 2]; 4] = [['a', '@'], ['s', '$'], ['d', '%'], ['f', '&']];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerRegistry {
+    symbols: Vec<[char; 2]>,
+}
+
+impl Default for PlayerRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_SYMBOLS.to_vec())
+    }
+}
+
+impl PlayerRegistry {
+    pub fn new(symbols: Vec<[char; 2]>) -> Self {
+        Self { symbols }
+    }
+
+    /// Parses a comma separated list of symbol pairs, e.g. `a@,s$,d%`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let symbols = spec
+            .split(',')
+            .map(|pair| {
+                let chars: Vec<char> = pair.trim().chars().collect();
+                match chars.as_slice() {
+                    [a, b] if *a != '.' && *b != '.' => Ok([*a, *b]),
+                    _ => Err(format!(
+                        "invalid symbol pair `{pair}`, expected two symbols"
+                    )),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let registry = Self::new(symbols);
+        for (i, pair) in registry.symbols.iter().enumerate() {
+            for c in pair {
+                if registry.owner(*c) != Some(i as i32 + 1) {
+                    return Err(format!("symbol `{c}` is used by several players"));
+                }
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = i32> {
+        1..=self.symbols.len() as i32
+    }
+
+    pub fn symbols(&self, id: i32) -> Option<[char; 2]> {
+        self.symbols.get((id - 1) as usize).copied()
+    }
+
+    /// Player owning a board symbol.
+    pub fn owner(&self, c: char) -> Option<i32> {
+        self.symbols
+            .iter()
+            .position(|s| s.contains(&c))
+            .map(|i| i as i32 + 1)
+    }
+
+    pub fn robot(&self, id: i32) -> Robot {
+        Robot::new(id, self.symbols(id).unwrap_or(['?', '?']))
+    }
+}
+
+impl fmt::Display for PlayerRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, [a, b]) in self.symbols.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{a}{b}")?;
+        }
+        Ok(())
+    }
+}
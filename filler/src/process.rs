@@ -5,7 +5,14 @@ use std::{
 
 use crate::{
     anfield::{Anfield, Cell},
-    logger::console_log,
+    book::{BookKey, OpeningBook},
+    debug,
+    dialect::Dialect,
+    info,
+    players::PlayerRegistry,
+    protocol::{ExecLine, ParseError, Turn, Validation},
+    score::{ScoreBreakdown, Term},
+    trace, Weights,
 };
 
 #[derive(Debug, Clone, Default)]
@@ -15,6 +22,7 @@ pub struct Robot {
     pub area: ((i32, i32), (i32, i32)),
     pub starting_point: (i32, i32),
     pub score: u32,
+    pub weights: Weights,
 }
 
 impl Robot {
@@ -25,6 +33,7 @@ impl Robot {
             area: ((0, 0), (0, 0)),
             starting_point: (0, 0),
             score: 0,
+            weights: Weights::default(),
         }
     }
 
@@ -34,11 +43,7 @@ impl Robot {
     }
 
     pub fn update_score(&mut self, anfield: &Anfield) {
-        self.score = anfield
-            .occupation
-            .iter()
-            .filter(|&(_, &id)| self.id == id)
-            .count() as u32
+        self.score = anfield.score(self.id)
     }
 }
 
@@ -61,15 +66,62 @@ impl Piece {
             cells,
         }
     }
+
+    /// Offset of the first filled cell, row by row; `(0, 0)` for an empty
+    /// piece.
+    pub fn anchor(&self) -> (i32, i32) {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .find(|&(x, y)| self.cells[y as usize][x as usize] != '.')
+            .unwrap_or((0, 0))
+    }
+}
+
+/// Number of turns the opponent cell count must stay the same before we
+/// consider it stuck. We never see the pieces the opponent is given, so
+/// whether it could still move is only known from whether it did.
+const STUCK_TURNS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evaluator {
+    /// Blocking, edge proximity and surround terms.
+    Heuristic,
+    /// Opponent can no longer move: fit as many pieces as possible.
+    Fill,
+}
+
+/// Where `State::choose_move` got its move from.
+#[derive(Debug, Clone, Copy)]
+pub enum Choice<'a> {
+    Book((i32, i32)),
+    /// Every legal position, best first.
+    Ranked(&'a [(Position, ScoreBreakdown)]),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct State {
     pub anfield: Anfield,
     pub robot: Robot,
-    pub opponent: Robot,
+    pub opponents: Vec<Robot>,
+    pub players: PlayerRegistry,
     pub current_piece: Piece,
     pub started: bool,
+    /// Total cell count of all opponents, per turn.
+    pub opponent_history: Vec<u32>,
+    pub opponent_stuck: bool,
+    /// Number of turns parsed so far.
+    pub turn: u32,
+    /// Evaluation weights given to our robot.
+    pub weights: Weights,
+    pub book: OpeningBook,
+    /// Player number to use instead of the one from the exec line.
+    pub player_override: Option<i32>,
+    /// What to do with a turn that disagrees with its own headers.
+    pub validation: Validation,
+    /// Format of the referee, detected from the first board.
+    pub dialect: Dialect,
+    /// `players` came from `--symbols` and is kept whatever the dialect.
+    pub symbols_given: bool,
 }
 
 impl State {
@@ -78,113 +130,82 @@ impl State {
         args.first()
             .unwrap()
             .split_terminator('/')
-            .last()
+            .next_back()
             .unwrap()
             .to_string()
     }
     pub fn new() -> Self {
-        Self {
-            anfield: Anfield::default(),
-            robot: Robot::default(),
-            current_piece: Piece::default(),
-            opponent: Robot::default(),
-            started: false,
-        }
+        Self::default()
     }
 
-    pub fn parse(&mut self, lines: Vec<String>) {
-        let mut anfield: Anfield = Anfield::new(0, 0);
-        let mut robot = if self.started {
-            Some(self.robot.clone())
-        } else {
-            None
-        };
-        let mut opponent = Robot::default();
-        let mut pieces_cells = Vec::new();
-        let mut parsing_pieces = false;
-
-        let mut parsing_anfield = false;
-        let mut anfield_strtidx: usize = 0;
-        for (idx, line) in lines.iter().enumerate() {
-            if line.starts_with("$$$") {
-                if line.contains("p1") && line.contains(&Self::prog_name()) {
-                    let r = Robot::new(1, ['a', '@']);
-                    robot = Some(r);
-                    opponent = Robot::new(2, ['s', '$']);
-                } else {
-                    let r = Robot::new(2, ['s', '$']);
-                    robot = Some(r);
-                    opponent = Robot::new(1, ['a', '@']);
+    /// Works out our player number from the exec lines of a turn. The
+    /// referee normally sends us only our own line; if it sends several, the
+    /// one naming our binary wins.
+    fn identify(&self, lines: &[String]) -> Result<i32, ParseError> {
+        if let Some(id) = self.player_override {
+            return Ok(id);
+        }
+        let execs = lines
+            .iter()
+            .filter(|l| l.starts_with("$$$"))
+            .map(|l| ExecLine::parse(l).ok_or_else(|| ParseError::InvalidExecLine(l.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        match execs.as_slice() {
+            [] => Err(ParseError::UnknownIdentity),
+            [exec] => Ok(exec.player),
+            _ => {
+                let prog = Self::prog_name();
+                let mine: Vec<_> = execs.iter().filter(|e| e.name() == prog).collect();
+                match mine.as_slice() {
+                    [exec] => Ok(exec.player),
+                    _ => Err(ParseError::AmbiguousIdentity(execs)),
                 }
-            } else if line.starts_with("Anfield") {
-                let part = line
-                    .trim_matches(|c: char| !c.is_numeric())
-                    .split_once(' ')
-                    .expect("error while spliting");
-                let width: i32 = part.0.parse().expect("error while parsing");
-                let height: i32 = part.1.parse().expect("error while parsing");
-                anfield = Anfield::new(width, height)
-            } else if line.trim().chars().all(char::is_numeric) {
-                parsing_anfield = true;
-                anfield_strtidx = idx + 1;
-                continue;
-            } else if line.starts_with("Piece") {
-                parsing_anfield = false;
-                parsing_pieces = true;
-                continue;
             }
-            if parsing_anfield {
-                let l =
-                    line.trim_matches(|c: char| !c.is_ascii_punctuation() && c != 'a' && c != 's');
-                l.char_indices().for_each(|(i, c)| {
-                    if c != '.' {
-                        if !self.started {
-                            if let Some(p) = robot.to_owned() {
-                                if p.characters.contains(&c) {
-                                    self.robot = p.to_owned();
-                                    self.robot.set_starting_point(
-                                        i as i32,
-                                        (idx - anfield_strtidx) as i32,
-                                    );
-                                } else {
-                                    self.opponent = opponent.to_owned();
-                                    self.opponent.set_starting_point(
-                                        i as i32,
-                                        (idx - anfield_strtidx) as i32,
-                                    );
-                                }
-                            }
-                        }
-                        if self.robot.characters.contains(&c) {
-                            anfield
-                                .occupation
-                                .insert((i as i32, (idx - anfield_strtidx) as i32), self.robot.id);
-                        } else {
-                            let pidx = if self.robot.id == 1 { 2 } else { 1 };
-                            anfield
-                                .occupation
-                                .insert((i as i32, (idx - anfield_strtidx) as i32), pidx);
+        }
+    }
+
+    pub fn parse(&mut self, lines: Vec<String>) -> Result<(), ParseError> {
+        let mut robot = None;
+        if let Some(dialect) = Dialect::detect(&lines).filter(|_| !self.started) {
+            info!("referee dialect: {dialect}");
+            self.dialect = dialect;
+            if !self.symbols_given {
+                self.players = dialect.players();
+            }
+            robot = Some(self.players.robot(self.identify(&lines)?));
+        }
+        let Turn { anfield, piece, .. } = Turn::parse(&lines, &self.players, self.validation)?;
+        if !self.started {
+            for y in 0..anfield.height {
+                for x in 0..anfield.width {
+                    match anfield.occupation.get(&(x, y)) {
+                        Some(&owner) if owner != 0 => {
+                            self.record_start(robot.as_ref(), owner, (x, y))
                         }
-                    } else {
-                        anfield
-                            .occupation
-                            .insert((i as i32, (idx - anfield_strtidx) as i32), 0);
+                        _ => {}
                     }
-                });
-            }
-
-            if parsing_pieces {
-                let l = line.trim();
-                let cells: Vec<char> = l.chars().collect();
-                pieces_cells.push(cells)
+                }
             }
         }
 
         if anfield.width != 0 {
+            self.robot.weights = self.weights;
             self.anfield = anfield;
+            self.anfield.update_opp_occupation(&self.robot);
+            self.robot.update_score(&self.anfield);
+            for opponent in self.opponents.iter_mut() {
+                opponent.update_score(&self.anfield);
+            }
+            self.opponent_history
+                .push(self.opponents.iter().map(|o| o.score).sum());
+            if !self.opponent_stuck && self.detect_opponent_stuck() {
+                info!("opponent is stuck, switching to the fill evaluator");
+                self.opponent_stuck = true;
+            }
         }
 
-        self.current_piece = Piece::new(pieces_cells);
+        self.current_piece = piece;
+        self.turn += 1;
         let ((x, y), (x1, y1)) = self.robot.area;
         self.robot.area = (
             (x - self.current_piece.width, y - self.current_piece.height),
@@ -194,6 +215,100 @@ impl State {
             ),
         );
         self.started = true;
+        Ok(())
+    }
+
+    /// Sets the starting point of the player owning a cell of the first
+    /// board, creating opponents as they are found.
+    fn record_start(&mut self, robot: Option<&Robot>, owner: i32, (x, y): (i32, i32)) {
+        if let Some(r) = robot.filter(|r| r.id == owner) {
+            self.robot = r.clone();
+            self.robot.set_starting_point(x, y);
+        } else if !self.opponents.iter().any(|o| o.id == owner) {
+            let mut opponent = self.players.robot(owner);
+            opponent.set_starting_point(x, y);
+            self.opponents.push(opponent);
+            self.opponents.sort_by_key(|o| o.id);
+        }
+    }
+
+    /// The opponents together have not gained a cell in the last
+    /// `STUCK_TURNS` turns.
+    fn detect_opponent_stuck(&self) -> bool {
+        if self.opponents.is_empty() {
+            return false;
+        }
+        let history = &self.opponent_history;
+        history.len() >= STUCK_TURNS
+            && history[history.len() - STUCK_TURNS..]
+                .iter()
+                .all(|&s| s == history[history.len() - 1])
+    }
+
+    pub fn evaluator(&self) -> Evaluator {
+        if self.opponent_stuck {
+            Evaluator::Fill
+        } else {
+            Evaluator::Heuristic
+        }
+    }
+
+    /// Origin from the opening book, if it has one for this turn and it is
+    /// legal.
+    pub fn book_move(&self) -> Option<(i32, i32)> {
+        let origin = self.book.get(&BookKey::from_state(self))?;
+        self.anfield
+            .can_place(origin, &self.robot, &self.current_piece)
+            .then_some(origin)
+    }
+
+    /// The origin to play this turn: the opening book's, else the best
+    /// ranked position, else `0 0` when the piece fits nowhere. `chosen`
+    /// is told where the move came from.
+    pub fn choose_move(&self, chosen: impl FnOnce(Choice)) -> (i32, i32) {
+        if let Some((x, y)) = self.book_move() {
+            debug!("playing {x} {y} from the opening book");
+            chosen(Choice::Book((x, y)));
+            return (x, y);
+        }
+        let positions = self.ranked_positions();
+        chosen(Choice::Ranked(&positions));
+        match positions.first() {
+            Some((p, breakdown)) => {
+                debug!("playing {} {} ({breakdown})", p.x, p.y);
+                (p.x, p.y)
+            }
+            None => (0, 0),
+        }
+    }
+
+    /// Legal positions for the current piece, best first. Ties go to the
+    /// topmost, then leftmost, position.
+    pub fn ranked_positions(&self) -> Vec<(Position, ScoreBreakdown)> {
+        let mut positions: Vec<_> = match self.evaluator() {
+            Evaluator::Heuristic => self
+                .anfield
+                .potential_positions(&self.current_piece, &self.robot)
+                .into_iter()
+                .collect(),
+            Evaluator::Fill => {
+                let mut anfield = self.anfield.clone();
+                self.anfield
+                    .potential_positions(&self.current_piece, &self.robot)
+                    .into_keys()
+                    .map(|p| {
+                        let score = p.fill_score(&mut anfield, &self.robot);
+                        (p, score)
+                    })
+                    .collect()
+            }
+        };
+        positions.sort_by(|a, b| {
+            b.1.total
+                .total_cmp(&a.1.total)
+                .then((a.0.y, a.0.x).cmp(&(b.0.y, b.0.x)))
+        });
+        positions
     }
 }
 
@@ -206,11 +321,11 @@ pub struct Position {
 }
 
 impl Position {
-    fn blocking_score(&self, anfield: &Anfield, coord: (i32, i32)) -> i32 {
+    fn blocking_score(&self, anfield: &Anfield, robot: &Robot, coord: (i32, i32)) -> i32 {
         let x = coord.0;
         let y = coord.1;
         let cell = Cell::new(self.x + x, self.y + y, self.robot_idx);
-        cell.blocking_potential(anfield)
+        cell.blocking_potential(anfield, &robot.weights)
     }
 
     fn edge_proximity(&self, anfield: &Anfield, coord: (i32, i32)) -> i32 {
@@ -232,12 +347,12 @@ impl Position {
             .for_each(|Cell { x, y, .. }| {
                 if let Some(id) = anfield.occupation.get(&(x, y)) {
                     if *id != 0 && *id != robot.id {
-                        let distance = (((self.x as i32 - x as i32) as f32).powf(2.0)
-                            + ((self.y as i32 - y as i32) as f32).powf(2.0))
+                        let distance = (((self.x - x) as f32).powf(2.0)
+                            + ((self.y - y) as f32).powf(2.0))
                         .abs()
                         .sqrt();
                         if distance < min_distance {
-                            min_distance = distance.into();
+                            min_distance = distance;
                         }
                     }
                     for di in -1..=1 {
@@ -262,33 +377,78 @@ impl Position {
                 }
             });
 
-        console_log(format!("surround: {}", (min_distance - score as f32).abs()));
+        trace!("surround: {}", (min_distance - score as f32).abs());
         (min_distance - (score) as f32).abs() as i32
     }
 
-    pub fn score(&self, anfield: &Anfield, robot: &Robot) -> f32 {
+    /// Endgame evaluation: hug walls and existing cells so that the open
+    /// space stays in one piece, and avoid leaving holes no piece can reach.
+    pub fn fill_score(&self, anfield: &mut Anfield, robot: &Robot) -> ScoreBreakdown {
+        let undo = anfield.place(&self.piece, (self.x, self.y), robot.id);
+        let free = |anfield: &Anfield, x: i32, y: i32| anfield.occupation.get(&(x, y)) == Some(&0);
+
+        let mut snug = 0;
+        let mut holes = 0;
+        for i in 0..self.piece.height {
+            for j in 0..self.piece.width {
+                if self.piece.cells[i as usize][j as usize] == '.' {
+                    continue;
+                }
+                let (x, y) = (self.x + j, self.y + i);
+                for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if !free(anfield, nx, ny) {
+                        snug += 1;
+                    } else if [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                        .iter()
+                        .all(|(ex, ey)| !free(anfield, nx + ex, ny + ey))
+                    {
+                        holes += 1;
+                    }
+                }
+            }
+        }
+
+        anfield.undo(undo);
+        ScoreBreakdown::new(vec![
+            Term::new("snug", snug as f32, 1.0),
+            Term::new("holes", holes as f32, -4.0),
+        ])
+    }
+
+    pub fn score(&self, anfield: &Anfield, robot: &Robot) -> ScoreBreakdown {
         let mut blocking_score = 0;
         let mut edge_proximity = 0;
 
         for i in 0..self.piece.height {
             for j in 0..self.piece.width {
                 if self.piece.cells[i as usize][j as usize] != '.' {
-                    blocking_score += self.blocking_score(anfield, (j, i));
+                    blocking_score += self.blocking_score(anfield, robot, (j, i));
                     edge_proximity += self.edge_proximity(anfield, (j, i));
                 }
             }
         }
-        let blocking_score = (blocking_score * 10) as f32;
-        let edge_proximity = 20 * edge_proximity / max(anfield.height, anfield.width);
+        // The weighted distance is divided by the board size in integers,
+        // as it always has been, so that the same moves are played.
+        let weight = robot.weights.edge_proximity;
+        let edge_contribution =
+            (weight * edge_proximity as f32) as i32 / max(anfield.height, anfield.width);
 
-        let mut score = blocking_score + edge_proximity as f32;
-        score += (self.surround_score(anfield, robot)) as f32 * 2.0;
-        console_log(format!("blocking_score: {blocking_score}"));
-        console_log(format!("edge_proximity: {edge_proximity}"));
-
-        console_log(format!("total: {score}"));
-
-        console_log(format!("position: {:?}", (self.x, self.y)));
-        score
+        let breakdown = ScoreBreakdown::new(vec![
+            Term::new("blocking", blocking_score as f32, robot.weights.blocking),
+            Term::with_contribution(
+                "edge_proximity",
+                edge_proximity as f32,
+                weight,
+                edge_contribution as f32,
+            ),
+            Term::new(
+                "surround",
+                self.surround_score(anfield, robot) as f32,
+                robot.weights.surround,
+            ),
+        ]);
+        trace!("position {:?}: {breakdown}", (self.x, self.y));
+        breakdown
     }
 }
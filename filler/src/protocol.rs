@@ -0,0 +1,572 @@
+//! The referee protocol: reading turns and writing them back, in either
+//! `Dialect`.
+//!
+//! The writers are the exact inverse of the readers: for a `Turn` built
+//! from claimed cells, `Turn::parse` of `turn.display(players)` gives back
+//! the same turn.
+
+use std::{
+    fmt,
+    io::{self, BufRead},
+};
+
+use crate::{dialect::Dialect, warn, Anfield, Piece, PlayerRegistry};
+
+/// A `$$$ exec p1 : [robots/bender]` line announcing a player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecLine {
+    pub player: i32,
+    pub path: String,
+}
+
+impl ExecLine {
+    pub fn parse(line: &str) -> Option<Self> {
+        let rest = line.strip_prefix("$$$")?.trim_start();
+        let rest = rest.strip_prefix("exec")?.trim_start();
+        let (player, path) = rest.split_once(':')?;
+        let player = player.trim().strip_prefix('p')?.parse().ok()?;
+        let path = path.trim().strip_prefix('[')?.strip_suffix(']')?;
+        Some(Self {
+            player,
+            path: path.to_string(),
+        })
+    }
+
+    /// Last component of `path`, which is what the binary sees as its name.
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+impl fmt::Display for ExecLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$$$ exec p{} : [{}]", self.player, self.path)
+    }
+}
+
+/// One message from the referee: exec lines, on the first turn only, then
+/// the board and the piece to place.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Turn {
+    /// The dialect of the board, `Anfield` if the message had none. A
+    /// `Plateau` turn without a board reads back as `Anfield`, as nothing
+    /// in a lone piece block tells the dialects apart.
+    pub dialect: Dialect,
+    pub execs: Vec<ExecLine>,
+    /// Empty (0 x 0) if the message had no board.
+    pub anfield: Anfield,
+    pub piece: Piece,
+}
+
+/// How `Turn::parse` treats a board or piece that disagrees with its
+/// header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Validation {
+    /// Any mismatch is an error.
+    #[default]
+    Strict,
+    /// Mismatches are logged; rows are cut or padded with empty cells to
+    /// the header's size and unknown symbols read as empty.
+    Lenient,
+}
+
+impl Validation {
+    /// Returns `err` in strict mode, logs it in lenient mode.
+    fn check(self, err: ParseError) -> Result<(), ParseError> {
+        match self {
+            Validation::Strict => Err(err),
+            Validation::Lenient => {
+                warn!("{err}, recovering");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The two blocks with a size header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Block {
+    Anfield,
+    Piece,
+}
+
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Block::Anfield => "board",
+            Block::Piece => "piece",
+        })
+    }
+}
+
+impl Turn {
+    pub fn parse<S: AsRef<str>>(
+        lines: &[S],
+        players: &PlayerRegistry,
+        validation: Validation,
+    ) -> Result<Self, ParseError> {
+        let lines: Vec<&str> = lines.iter().map(AsRef::as_ref).collect();
+        let mut turn = Turn::default();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            i += 1;
+            if line.starts_with("$$$") {
+                let exec = ExecLine::parse(line)
+                    .ok_or_else(|| ParseError::InvalidExecLine(line.to_string()))?;
+                turn.execs.push(exec);
+            } else if let Some(dialect) = Dialect::of_header(line) {
+                turn.dialect = dialect;
+                let size = dialect.size(header(line)?);
+                let end = lines[i..]
+                    .iter()
+                    .position(|l| l.starts_with("Piece"))
+                    .map_or(lines.len(), |n| i + n);
+                let mut rows = &lines[i..end];
+                // The column index line.
+                if rows.first().is_some_and(|l| {
+                    !l.trim().is_empty() && l.trim().chars().all(|c| c.is_ascii_digit())
+                }) {
+                    rows = &rows[1..];
+                }
+                turn.anfield = parse_anfield(size, rows, players, validation)?;
+                i = end;
+            } else if line.starts_with("Piece") {
+                let size = turn.dialect.size(header(line)?);
+                let end = lines[i..]
+                    .iter()
+                    .take(size.1.max(0) as usize)
+                    .position(|l| {
+                        l.starts_with("$$$")
+                            || l.starts_with("Piece")
+                            || Dialect::of_header(l).is_some()
+                    })
+                    .map_or((i + size.1.max(0) as usize).min(lines.len()), |n| i + n);
+                turn.piece = parse_piece(size, &lines[i..end], turn.dialect, validation)?;
+                i = end;
+            }
+        }
+        Ok(turn)
+    }
+
+    pub fn display<'a>(&'a self, players: &'a PlayerRegistry) -> TurnText<'a> {
+        TurnText {
+            turn: self,
+            players,
+        }
+    }
+}
+
+/// The two numbers of a board or piece header, in the dialect's order.
+fn header(line: &str) -> Result<(i32, i32), ParseError> {
+    let invalid = || ParseError::InvalidHeader(line.to_string());
+    let sizes: Vec<&str> = line
+        .trim_end()
+        .strip_suffix(':')
+        .ok_or_else(invalid)?
+        .split_whitespace()
+        .skip(1)
+        .collect();
+    match sizes.as_slice() {
+        [w, h] => match (w.parse(), h.parse()) {
+            (Ok(w), Ok(h)) if w >= 0 && h >= 0 => Ok((w, h)),
+            _ => Err(invalid()),
+        },
+        _ => Err(invalid()),
+    }
+}
+
+/// Cells of `line` cut or padded with `.` to `width`.
+fn fit_row(
+    block: Block,
+    row: i32,
+    cells: &str,
+    width: i32,
+    validation: Validation,
+) -> Result<Vec<char>, ParseError> {
+    let mut cells: Vec<char> = cells.chars().collect();
+    if cells.len() != width as usize {
+        validation.check(ParseError::RowLength {
+            block,
+            row,
+            expected: width,
+            found: cells.len(),
+        })?;
+        cells.resize(width as usize, '.');
+    }
+    Ok(cells)
+}
+
+fn parse_anfield(
+    (width, height): (i32, i32),
+    rows: &[&str],
+    players: &PlayerRegistry,
+    validation: Validation,
+) -> Result<Anfield, ParseError> {
+    if rows.len() != height as usize {
+        validation.check(ParseError::RowCount {
+            block: Block::Anfield,
+            expected: height,
+            found: rows.len(),
+        })?;
+    }
+    // Rows go where their number says, so that one missing row does not
+    // shift the others.
+    let mut numbered: Vec<Option<&str>> = vec![None; height as usize];
+    for (n, line) in rows.iter().enumerate() {
+        let n = n as i32;
+        let line = line.trim();
+        let prefix = line
+            .split_once(' ')
+            .and_then(|(number, cells)| Some((number.parse::<i32>().ok()?, cells.trim())));
+        let (y, cells) = match prefix {
+            Some((y, cells)) if y == n => (y, cells),
+            _ => {
+                validation.check(ParseError::RowNumber {
+                    row: n,
+                    line: line.to_string(),
+                })?;
+                match prefix {
+                    Some((y, cells)) if (0..height).contains(&y) => (y, cells),
+                    _ => (
+                        n,
+                        line.trim_start_matches(|c: char| c.is_ascii_digit()).trim(),
+                    ),
+                }
+            }
+        };
+        if let Some(slot) = numbered.get_mut(y as usize) {
+            *slot = Some(cells);
+        }
+    }
+
+    let mut anfield = Anfield::new(width, height);
+    for (y, cells) in numbered.into_iter().enumerate() {
+        let y = y as i32;
+        // A missing row was already reported as a row count mismatch.
+        let cells = match cells {
+            Some(cells) => fit_row(Block::Anfield, y, cells, width, validation)?,
+            None => vec!['.'; width as usize],
+        };
+        for (x, c) in cells.into_iter().enumerate() {
+            let x = x as i32;
+            let owner = match c {
+                '.' => 0,
+                c => match players.owner(c) {
+                    Some(owner) => owner,
+                    None => {
+                        validation.check(ParseError::UnknownSymbol {
+                            block: Block::Anfield,
+                            symbol: c,
+                            x,
+                            y,
+                        })?;
+                        0
+                    }
+                },
+            };
+            if owner != 0 && players.symbols(owner).map(|s| s[0]) == Some(c) {
+                anfield.recent.insert((x, y));
+            }
+            anfield.claim((x, y), owner);
+        }
+    }
+    Ok(anfield)
+}
+
+/// Filled cells are stored as `O` whatever the dialect.
+fn parse_piece(
+    (width, height): (i32, i32),
+    rows: &[&str],
+    dialect: Dialect,
+    validation: Validation,
+) -> Result<Piece, ParseError> {
+    if rows.len() != height as usize {
+        validation.check(ParseError::RowCount {
+            block: Block::Piece,
+            expected: height,
+            found: rows.len(),
+        })?;
+    }
+    let mut cells = Vec::new();
+    for y in 0..height {
+        let line = rows.get(y as usize).map_or("", |l| l.trim());
+        let mut row = fit_row(Block::Piece, y, line, width, validation)?;
+        for (x, c) in row.iter_mut().enumerate() {
+            if *c == dialect.piece_cell() {
+                *c = 'O';
+            } else if *c != '.' {
+                validation.check(ParseError::UnknownSymbol {
+                    block: Block::Piece,
+                    symbol: *c,
+                    x: x as i32,
+                    y,
+                })?;
+                *c = '.';
+            }
+        }
+        cells.push(row);
+    }
+    Ok(Piece::new(cells))
+}
+
+/// A `Turn` as the referee would send it.
+pub struct TurnText<'a> {
+    turn: &'a Turn,
+    players: &'a PlayerRegistry,
+}
+
+impl fmt::Display for TurnText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for exec in &self.turn.execs {
+            writeln!(f, "{exec}")?;
+        }
+        let dialect = self.turn.dialect;
+        if self.turn.anfield.width > 0 {
+            let anfield = AnfieldText::new(&self.turn.anfield, self.players).dialect(dialect);
+            write!(f, "{anfield}")?;
+        }
+        write!(f, "{}", PieceText::new(&self.turn.piece, dialect))
+    }
+}
+
+/// The `Anfield W H:` block: a column index line, then one row per line
+/// prefixed with its zero-padded number. Cells use the second symbol of
+/// their owner, or the first one if they belong to its last piece.
+pub struct AnfieldText<'a> {
+    anfield: &'a Anfield,
+    players: &'a PlayerRegistry,
+    dialect: Dialect,
+}
+
+impl<'a> AnfieldText<'a> {
+    pub fn new(anfield: &'a Anfield, players: &'a PlayerRegistry) -> Self {
+        Self {
+            anfield,
+            players,
+            dialect: Dialect::Anfield,
+        }
+    }
+
+    /// Writes the header in `dialect` instead.
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+}
+
+impl fmt::Display for AnfieldText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let anfield = self.anfield;
+        let (a, b) = self.dialect.size((anfield.width, anfield.height));
+        writeln!(f, "{} {a} {b}:", self.dialect.keyword())?;
+        let index: String = (0..anfield.width)
+            .map(|i| char::from_digit((i % 10) as u32, 10).unwrap_or('0'))
+            .collect();
+        writeln!(f, "    {index}")?;
+        for y in 0..anfield.height {
+            write!(f, "{:03} ", y)?;
+            for x in 0..anfield.width {
+                let c = match anfield.occupation.get(&(x, y)) {
+                    Some(&id) if id != 0 => {
+                        let recent = anfield.recent.contains(&(x, y));
+                        self.players
+                            .symbols(id)
+                            .map_or('?', |s| if recent { s[0] } else { s[1] })
+                    }
+                    _ => '.',
+                };
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The `Piece W H:` block.
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", PieceText::new(self, Dialect::Anfield))
+    }
+}
+
+/// A piece block in a given dialect, filled cells written with its own
+/// symbol.
+pub struct PieceText<'a> {
+    piece: &'a Piece,
+    dialect: Dialect,
+}
+
+impl<'a> PieceText<'a> {
+    pub fn new(piece: &'a Piece, dialect: Dialect) -> Self {
+        Self { piece, dialect }
+    }
+}
+
+impl fmt::Display for PieceText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (a, b) = self.dialect.size((self.piece.width, self.piece.height));
+        writeln!(f, "Piece {a} {b}:")?;
+        for row in &self.piece.cells {
+            let row: String = row
+                .iter()
+                .map(|&c| {
+                    if c == '.' {
+                        c
+                    } else {
+                        self.dialect.piece_cell()
+                    }
+                })
+                .collect();
+            writeln!(f, "{row}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `p1`, `p2`, ... or a bare player number.
+pub fn parse_player(s: &str) -> Option<i32> {
+    let s = s.trim();
+    s.strip_prefix('p')
+        .unwrap_or(s)
+        .parse()
+        .ok()
+        .filter(|&n| n > 0)
+}
+
+/// Reads one turn: every line up to and including the rows of the next
+/// piece block, whose height is read in the dialect of the board before
+/// it. Whatever is left when the input ends is returned as a last, partial
+/// turn; `None` means there was nothing left at all.
+pub fn read_turn(input: &mut impl BufRead) -> io::Result<Option<Vec<String>>> {
+    let mut lines = Vec::new();
+    let mut dialect = Dialect::default();
+    let mut piece_rows: Option<usize> = None;
+    while piece_rows != Some(0) {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok((!lines.is_empty()).then_some(lines));
+        }
+        let line = line.trim_end_matches(['\n', '\r']).to_string();
+        dialect = Dialect::of_header(&line).unwrap_or(dialect);
+        piece_rows = match piece_rows {
+            Some(n) => Some(n - 1),
+            None if line.starts_with("Piece") => Some(piece_height(&line, dialect)?),
+            None => None,
+        };
+        lines.push(line);
+    }
+    Ok(Some(lines))
+}
+
+fn piece_height(line: &str, dialect: Dialect) -> io::Result<usize> {
+    line.trim_matches(|c: char| !c.is_numeric())
+        .split_once(' ')
+        .and_then(|(a, b)| {
+            dialect
+                .size((a.parse().ok()?, b.parse().ok()?))
+                .1
+                .try_into()
+                .ok()
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid piece header `{line}`"),
+            )
+        })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// A line starting with `$$$` that is not `$$$ exec pN : [path]`.
+    InvalidExecLine(String),
+    /// No exec line and no explicit player.
+    UnknownIdentity,
+    /// Several exec lines and none or many of them match our binary name.
+    AmbiguousIdentity(Vec<ExecLine>),
+    /// A board or piece header without two sizes.
+    InvalidHeader(String),
+    /// The block has `found` rows where its header says `expected`.
+    RowCount {
+        block: Block,
+        expected: i32,
+        found: usize,
+    },
+    /// Row `row` has `found` cells where the header says `expected`.
+    RowLength {
+        block: Block,
+        row: i32,
+        expected: i32,
+        found: usize,
+    },
+    /// A board row that does not start with its own number.
+    RowNumber { row: i32, line: String },
+    /// A symbol no player owns on the board, or other than `.` and the
+    /// dialect's filled cell in a piece.
+    UnknownSymbol {
+        block: Block,
+        symbol: char,
+        x: i32,
+        y: i32,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidExecLine(line) => write!(f, "invalid exec line `{line}`"),
+            ParseError::UnknownIdentity => write!(
+                f,
+                "could not establish which player we are: no `$$$ exec` line, \
+                 use --player p1|p2"
+            ),
+            ParseError::AmbiguousIdentity(lines) => {
+                write!(f, "could not tell which of these players we are: ")?;
+                for (i, l) in lines.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "`{l}`")?;
+                }
+                write!(f, "; use --player p1|p2")
+            }
+            ParseError::InvalidHeader(line) => {
+                write!(
+                    f,
+                    "invalid header `{line}`, expected `<Anfield|Piece> W H:` \
+                     or `<Plateau|Piece> H W:`"
+                )
+            }
+            ParseError::RowCount {
+                block,
+                expected,
+                found,
+            } => write!(f, "{block} has {found} rows, its header says {expected}"),
+            ParseError::RowLength {
+                block,
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{block} row {row} has {found} cells, the header says {expected}"
+            ),
+            ParseError::RowNumber { row, line } => {
+                write!(
+                    f,
+                    "board row {row} does not start with its number: `{line}`"
+                )
+            }
+            ParseError::UnknownSymbol {
+                block,
+                symbol,
+                x,
+                y,
+            } => write!(f, "unknown symbol `{symbol}` in the {block} at {x} {y}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,129 @@
+use std::fmt;
+
+use crate::{Evaluator, Position};
+
+/// One weighted term of an evaluation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub name: &'static str,
+    pub value: f32,
+    pub weight: f32,
+    contribution: f32,
+}
+
+impl Term {
+    pub fn new(name: &'static str, value: f32, weight: f32) -> Self {
+        Self::with_contribution(name, value, weight, value * weight)
+    }
+
+    /// A term whose share of the total is not exactly `value * weight`,
+    /// for evaluations that round along the way.
+    pub fn with_contribution(
+        name: &'static str,
+        value: f32,
+        weight: f32,
+        contribution: f32,
+    ) -> Self {
+        Self {
+            name,
+            value,
+            weight,
+            contribution,
+        }
+    }
+
+    pub fn contribution(&self) -> f32 {
+        self.contribution
+    }
+}
+
+/// Every term that went into a position score, and their weighted sum.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScoreBreakdown {
+    pub terms: Vec<Term>,
+    pub total: f32,
+}
+
+impl ScoreBreakdown {
+    pub fn new(terms: Vec<Term>) -> Self {
+        let total = terms.iter().map(Term::contribution).sum();
+        Self { terms, total }
+    }
+
+    pub fn term(&self, name: &str) -> Option<&Term> {
+        self.terms.iter().find(|t| t.name == name)
+    }
+
+    pub fn to_json(&self) -> String {
+        let terms: Vec<String> = self
+            .terms
+            .iter()
+            .map(|t| {
+                format!(
+                    r#"{{"name":"{}","value":{},"weight":{},"contribution":{}}}"#,
+                    t.name,
+                    json_number(t.value),
+                    json_number(t.weight),
+                    json_number(t.contribution())
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"total":{},"terms":[{}]}}"#,
+            json_number(self.total),
+            terms.join(",")
+        )
+    }
+}
+
+impl fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, t) in self.terms.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" + ")?;
+            }
+            write!(f, "{} {} x {}", t.name, t.value, t.weight)?;
+        }
+        write!(f, " = {}", self.total)
+    }
+}
+
+/// One JSON object describing the ranked candidates of a turn.
+pub fn candidates_json(
+    turn: u32,
+    evaluator: Evaluator,
+    candidates: &[(Position, ScoreBreakdown)],
+) -> String {
+    let candidates: Vec<String> = candidates
+        .iter()
+        .enumerate()
+        .map(|(rank, (p, breakdown))| {
+            format!(
+                r#"{{"rank":{},"x":{},"y":{},"score":{}}}"#,
+                rank + 1,
+                p.x,
+                p.y,
+                breakdown.to_json()
+            )
+        })
+        .collect();
+    format!(
+        r#"{{"turn":{},"source":"ranked","evaluator":"{:?}","candidates":[{}]}}"#,
+        turn,
+        evaluator,
+        candidates.join(",")
+    )
+}
+
+/// One JSON object for a turn played from the opening book.
+pub fn book_json(turn: u32, (x, y): (i32, i32)) -> String {
+    format!(r#"{{"turn":{turn},"source":"book","x":{x},"y":{y}}}"#)
+}
+
+fn json_number(v: f32) -> String {
+    if v.is_finite() {
+        v.to_string()
+    } else {
+        "null".to_string()
+    }
+}
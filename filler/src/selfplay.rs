@@ -0,0 +1,162 @@
+//! Local games between two weight sets, without a referee. Each side is a
+//! `State` fed the turns a referee would send, so self-play runs the same
+//! move selection as the bot.
+
+use crate::{
+    game::{Game, Move},
+    protocol::Turn,
+    Anfield, Piece, PlayerRegistry, State, Weights,
+};
+
+/// Small xorshift generator, enough to draw pieces and perturbations
+/// reproducibly.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Uniform in `0..n`.
+    pub fn below(&mut self, n: u32) -> u32 {
+        (self.next_u64() % n as u64) as u32
+    }
+
+    pub fn coin(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+}
+
+/// A random connected piece of up to five cells, padded with empty rows
+/// and columns like the referee does.
+pub fn random_piece(rng: &mut Rng) -> Piece {
+    let size = 1 + rng.below(5) as usize;
+    let mut cells = vec![(0i32, 0i32)];
+    while cells.len() < size {
+        let (x, y) = cells[rng.below(cells.len() as u32) as usize];
+        let (dx, dy) = [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.below(4) as usize];
+        if !cells.contains(&(x + dx, y + dy)) {
+            cells.push((x + dx, y + dy));
+        }
+    }
+    let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0) - rng.below(2) as i32;
+    let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0) - rng.below(2) as i32;
+    let max_x = cells.iter().map(|c| c.0).max().unwrap_or(0) + rng.below(2) as i32;
+    let max_y = cells.iter().map(|c| c.1).max().unwrap_or(0) + rng.below(2) as i32;
+
+    let rows = (min_y..=max_y)
+        .map(|y| {
+            (min_x..=max_x)
+                .map(|x| if cells.contains(&(x, y)) { 'O' } else { '.' })
+                .collect()
+        })
+        .collect();
+    Piece::new(rows)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayedMove {
+    /// Index of the weight set that played the move.
+    pub player: usize,
+    /// Move number of that player, starting at 1.
+    pub turn: u32,
+    pub piece: Piece,
+    pub origin: (i32, i32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub scores: [u32; 2],
+    /// Index of the winning weight set, `None` on a draw.
+    pub winner: Option<usize>,
+    pub moves: Vec<PlayedMove>,
+}
+
+/// Plays one game on an empty `width` x `height` board. `players[0]` is
+/// player 1 and starts at `starts[0]`. A player whose answer is not a legal
+/// move is out, as with the referee.
+pub fn play(
+    width: i32,
+    height: i32,
+    starts: [(i32, i32); 2],
+    players: [Weights; 2],
+    rng: &mut Rng,
+) -> Outcome {
+    let mut anfield = Anfield::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            anfield.claim((x, y), 0);
+        }
+    }
+    for (i, &start) in starts.iter().enumerate() {
+        anfield.claim(start, i as i32 + 1);
+    }
+    let mut states = players.map(|weights| State {
+        weights,
+        ..State::default()
+    });
+    for (i, state) in states.iter_mut().enumerate() {
+        state.player_override = Some(i as i32 + 1);
+    }
+    let mut game = Game::new(anfield, 2);
+    let symbols = PlayerRegistry::default();
+
+    let mut moves = Vec::new();
+    let mut played = [0; 2];
+    let mut turn = 0;
+    while let Some(player) = game.to_move() {
+        if turn >= width * height {
+            break;
+        }
+        turn += 1;
+        let i = (player - 1) as usize;
+        let piece = random_piece(rng);
+        let text = Turn {
+            anfield: game.anfield().clone(),
+            piece: piece.clone(),
+            ..Turn::default()
+        }
+        .display(&symbols)
+        .to_string();
+        states[i]
+            .parse(text.lines().map(String::from).collect())
+            .expect("turns written by `Turn::display` parse");
+        let origin = states[i].choose_move(|_| {});
+
+        let mv = Move::Place {
+            player,
+            piece: piece.clone(),
+            origin,
+        };
+        if game.apply(mv).is_ok() {
+            played[i] += 1;
+            moves.push(PlayedMove {
+                player: i,
+                turn: played[i],
+                piece,
+                origin,
+            });
+        } else {
+            game.apply(Move::Pass { player })
+                .expect("the player to move can always pass");
+        }
+    }
+
+    let scores = [game.anfield().score(1), game.anfield().score(2)];
+    let winner = game.winner().map(|p| (p - 1) as usize);
+    Outcome {
+        scores,
+        winner,
+        moves,
+    }
+}
@@ -0,0 +1,163 @@
+//! Session logs: every line read from the referee and every answer, each
+//! prefixed with the milliseconds since the session started and `<` for
+//! input or `>` for output. `#` lines are comments, except `# player pN`
+//! and `# dialect name`, written once the first turn is parsed, which let
+//! a replay identify itself the way the live game did.
+
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, LineWriter, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{dialect::Dialect, State};
+
+pub struct Recorder {
+    file: LineWriter<File>,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut file = LineWriter::new(File::create(path)?);
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        writeln!(
+            file,
+            "# filler session started at {since_epoch} (unix time)"
+        )?;
+        Ok(Self {
+            file,
+            start: Instant::now(),
+        })
+    }
+
+    /// Records who we played as and the referee's dialect.
+    pub fn identity(&mut self, player: i32, dialect: Dialect) -> io::Result<()> {
+        writeln!(self.file, "# player p{player}")?;
+        writeln!(self.file, "# dialect {dialect}")
+    }
+
+    pub fn input(&mut self, line: &str) -> io::Result<()> {
+        self.write('<', line)
+    }
+
+    pub fn output(&mut self, line: &str) -> io::Result<()> {
+        self.write('>', line)
+    }
+
+    fn write(&mut self, direction: char, line: &str) -> io::Result<()> {
+        let ms = self.start.elapsed().as_millis();
+        writeln!(self.file, "{ms} {direction} {line}")
+    }
+}
+
+/// A turn where the move chosen now is not the recorded one.
+pub struct Change {
+    pub turn: usize,
+    pub ms: u64,
+    pub recorded: String,
+    pub now: String,
+}
+
+pub struct Replay {
+    pub turns: usize,
+    pub changes: Vec<Change>,
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in &self.changes {
+            writeln!(
+                f,
+                "turn {} ({:.3} s): recorded `{}`, now `{}`",
+                c.turn,
+                c.ms as f64 / 1000.0,
+                c.recorded,
+                c.now
+            )?;
+        }
+        writeln!(
+            f,
+            "{} turns replayed, {} changed",
+            self.turns,
+            self.changes.len()
+        )
+    }
+}
+
+/// Feeds the recorded input to `state` turn by turn and compares each
+/// answer with the recorded one. `state` carries the options to play
+/// with, which the log does not keep; the recorded player is used unless
+/// `state` already has one.
+pub fn replay(path: &Path, mut state: State) -> Result<Replay, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+    let mut replay = Replay {
+        turns: 0,
+        changes: Vec::new(),
+    };
+    let mut input = Vec::new();
+    let mut dialect = None;
+    for (n, line) in text.lines().enumerate() {
+        let invalid = || {
+            format!(
+                "{}:{}: invalid session line `{line}`",
+                path.display(),
+                n + 1
+            )
+        };
+        if let Some(comment) = line.strip_prefix('#') {
+            match comment.trim().split_once(' ') {
+                Some(("player", id)) if state.player_override.is_none() => {
+                    let id = id.strip_prefix('p').and_then(|id| id.parse().ok());
+                    state.player_override = Some(id.ok_or_else(invalid)?);
+                }
+                Some(("dialect", name)) => {
+                    let found = Dialect::ALL.into_iter().find(|d| d.to_string() == name);
+                    dialect = Some(found.ok_or_else(invalid)?);
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (ms, rest) = line.split_once(' ').ok_or_else(invalid)?;
+        let ms: u64 = ms.parse().map_err(|_| invalid())?;
+        if let Some(rest) = rest.strip_prefix('<') {
+            input.push(rest.strip_prefix(' ').unwrap_or(rest).to_string());
+            continue;
+        }
+        let recorded = rest.strip_prefix('>').ok_or_else(invalid)?.trim();
+        if input.is_empty() {
+            continue;
+        }
+        replay.turns += 1;
+        state
+            .parse(std::mem::take(&mut input))
+            .map_err(|err| format!("{}: turn {}: {err}", path.display(), replay.turns))?;
+        if let Some(recorded) = dialect.filter(|&d| d != state.dialect) {
+            return Err(format!(
+                "{}: recorded with the {recorded} dialect, but the turns read as {}",
+                path.display(),
+                state.dialect
+            ));
+        }
+        let (x, y) = state.choose_move(|_| {});
+        let now = state.dialect.answer(x, y);
+        if now != recorded {
+            replay.changes.push(Change {
+                turn: replay.turns,
+                ms,
+                recorded: recorded.to_string(),
+                now,
+            });
+        }
+    }
+    Ok(replay)
+}
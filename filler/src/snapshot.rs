@@ -0,0 +1,87 @@
+//! Single positions loaded from a file, either a saved referee turn or a
+//! map-file board followed by a `Piece W H:` block.
+
+use std::{fs, io, path::Path};
+
+use crate::{
+    dialect::Dialect,
+    protocol::{AnfieldText, ParseError},
+    Anfield, Piece, PlayerRegistry, State,
+};
+
+pub fn load(path: &Path) -> io::Result<State> {
+    parse(&fs::read_to_string(path)?).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn parse(text: &str) -> Result<State, ParseError> {
+    parse_into(State::new(), text)
+}
+
+/// Parses a snapshot into `state`, which carries the weights and player
+/// override to use. Snapshots without an exec line are played as p1.
+pub fn parse_into(mut state: State, text: &str) -> Result<State, ParseError> {
+    if !text.lines().any(|l| l.starts_with("$$$")) && state.player_override.is_none() {
+        state.player_override = Some(1);
+    }
+    state.parse(turn_lines(text))?;
+    Ok(state)
+}
+
+/// Turns a snapshot into the lines the referee would have sent us.
+fn turn_lines(text: &str) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    let mut out: Vec<String> = lines
+        .iter()
+        .filter(|l| l.starts_with("$$$"))
+        .map(|l| l.to_string())
+        .collect();
+
+    if Dialect::detect(&lines).is_some() {
+        out.extend(
+            lines
+                .iter()
+                .filter(|l| !l.starts_with("$$$"))
+                .map(|l| l.to_string()),
+        );
+        return out;
+    }
+
+    let piece_start = lines
+        .iter()
+        .position(|l| l.starts_with("Piece"))
+        .unwrap_or(lines.len());
+    let rows: Vec<&str> = lines[..piece_start]
+        .iter()
+        .filter(|l| !l.starts_with("$$$"))
+        .map(|l| l.trim())
+        .collect();
+    let width = rows.first().map_or(0, |r| r.chars().count());
+
+    out.push(format!("Anfield {} {}:", width, rows.len()));
+    out.push(format!(
+        "    {}",
+        (0..width).map(|i| (i % 10).to_string()).collect::<String>()
+    ));
+    out.extend(
+        rows.iter()
+            .enumerate()
+            .map(|(i, row)| format!("{:03} {row}", i)),
+    );
+    out.extend(lines[piece_start..].iter().map(|l| l.to_string()));
+    out
+}
+
+/// Renders a position the way the referee would send it, minus the exec
+/// line, so that `parse` plays it as p1.
+pub fn write(anfield: &Anfield, piece: &Piece, players: &PlayerRegistry) -> String {
+    format!("{}{piece}", AnfieldText::new(anfield, players))
+}
+
+pub fn save(
+    path: &Path,
+    anfield: &Anfield,
+    piece: &Piece,
+    players: &PlayerRegistry,
+) -> io::Result<()> {
+    fs::write(path, write(anfield, piece, players))
+}
@@ -0,0 +1,110 @@
+//! Tunable constants of the evaluation, and the `name = value` file format
+//! the `tune` binary writes them in.
+
+use std::{fmt, fs, io, path::Path};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    /// Weight of the `blocking` term of `Position::score`.
+    pub blocking: f32,
+    /// Weight of the `edge_proximity` term of `Position::score`, whose
+    /// contribution is then divided by the board size in integers.
+    pub edge_proximity: f32,
+    /// Weight of the `surround` term of `Position::score`.
+    pub surround: f32,
+    /// Reward per free cell around an opponent cell in
+    /// `Cell::blocking_potential`.
+    pub blocking_neighbour: f32,
+    /// An opponent cell needs more free neighbours than this to be part of
+    /// `Anfield::opp_occupation`.
+    pub frontier_min_free: f32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            blocking: 10.0,
+            edge_proximity: 20.0,
+            surround: 2.0,
+            blocking_neighbour: 20.0,
+            frontier_min_free: 2.0,
+        }
+    }
+}
+
+impl Weights {
+    pub const NAMES: [&'static str; 5] = [
+        "blocking",
+        "edge_proximity",
+        "surround",
+        "blocking_neighbour",
+        "frontier_min_free",
+    ];
+
+    pub fn to_vec(&self) -> Vec<f32> {
+        vec![
+            self.blocking,
+            self.edge_proximity,
+            self.surround,
+            self.blocking_neighbour,
+            self.frontier_min_free,
+        ]
+    }
+
+    pub fn from_slice(v: &[f32]) -> Self {
+        let mut w = Self::default();
+        for (name, &value) in Self::NAMES.iter().zip(v) {
+            w.set(name, value);
+        }
+        w
+    }
+
+    fn set(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "blocking" => self.blocking = value,
+            "edge_proximity" => self.edge_proximity = value,
+            "surround" => self.surround = value,
+            "blocking_neighbour" => self.blocking_neighbour = value,
+            "frontier_min_free" => self.frontier_min_free = value,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Parses `name = value` lines. Missing names keep their default value,
+    /// `#` starts a comment.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut w = Self::default();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `name = value`", n + 1))?;
+            let value: f32 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("line {}: invalid number `{}`", n + 1, value.trim()))?;
+            if !w.set(name.trim(), value) {
+                return Err(format!("line {}: unknown weight `{}`", n + 1, name.trim()));
+            }
+        }
+        Ok(w)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in Self::NAMES.iter().zip(self.to_vec()) {
+            writeln!(f, "{name} = {value}")?;
+        }
+        Ok(())
+    }
+}
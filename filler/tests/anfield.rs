@@ -0,0 +1,75 @@
+use filler::{Anfield, Piece, Robot};
+
+/// Board from rows of `.`, `1` and `2`.
+fn board(rows: &[&str]) -> Anfield {
+    let mut anfield = Anfield::new(rows[0].len() as i32, rows.len() as i32);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            let id = c.to_digit(10).unwrap_or(0) as i32;
+            anfield.claim((x as i32, y as i32), id);
+        }
+    }
+    anfield
+}
+
+fn pieces() -> Vec<Piece> {
+    [
+        vec!["O"],
+        vec!["OO"],
+        vec![".O", "OO"],
+        vec!["...", ".O.", ".OO"],
+        vec!["O.O", "OOO"],
+    ]
+    .into_iter()
+    .map(|rows| Piece::new(rows.iter().map(|r| r.chars().collect()).collect()))
+    .collect()
+}
+
+#[test]
+fn undo_restores_the_board() {
+    let mut anfield = board(&[
+        "..........",
+        ".11.......",
+        ".1........",
+        "......2...",
+        ".....22...",
+        "......2...",
+        "..........",
+    ]);
+    let robot = Robot::new(1, ['a', '@']);
+    anfield.update_opp_occupation(&robot);
+    let before = anfield.clone();
+    // Every origin, legal or not, for both players.
+    for piece in pieces() {
+        for y in -2..anfield.height {
+            for x in -2..anfield.width {
+                for player in 1..=2 {
+                    let undo = anfield.place(&piece, (x, y), player);
+                    anfield.undo(undo);
+                    assert_eq!(anfield.occupation, before.occupation);
+                    assert_eq!(anfield.scores, before.scores);
+                    assert_eq!(anfield.opp_occupation, before.opp_occupation);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn nested_undos_restore_the_board() {
+    // Player 2 has no cell yet, so its score appears and must go again.
+    let mut anfield = board(&["........", ".1......", "........", "........", "........"]);
+    anfield.update_opp_occupation(&Robot::new(1, ['a', '@']));
+    let before = anfield.clone();
+    let mut undos = Vec::new();
+    for (n, piece) in pieces().iter().enumerate() {
+        let origin = (n as i32, n as i32 % 3);
+        undos.push(anfield.place(piece, origin, 1 + n as i32 % 2));
+    }
+    while let Some(undo) = undos.pop() {
+        anfield.undo(undo);
+    }
+    assert_eq!(anfield.occupation, before.occupation);
+    assert_eq!(anfield.scores, before.scores);
+    assert_eq!(anfield.opp_occupation, before.opp_occupation);
+}
@@ -0,0 +1,47 @@
+use filler::{
+    book::{BookKey, OpeningBook},
+    snapshot, State,
+};
+
+const P2_FIRST_TURN: &str = "\
+Anfield 6 5:
+    012345
+000 ......
+001 .aa...
+002 .a@...
+003 ......
+004 ....$.
+Piece 2 1:
+OO
+";
+
+#[test]
+fn key_uses_our_start_as_p2() {
+    let state = State {
+        player_override: Some(2),
+        ..State::default()
+    };
+    let state = snapshot::parse_into(state, P2_FIRST_TURN).unwrap();
+    let key = BookKey::from_state(&state);
+    assert_eq!(key.size, (6, 5));
+    assert_eq!(key.start, (4, 4));
+    assert_eq!(key.turn, 1);
+    assert_eq!(key.piece, "OO");
+}
+
+#[test]
+fn reads_both_formats() {
+    let book = OpeningBook::parse("6 5 4 4 1 OO 3 3\n6 5 1 1 4 4 2 O. 0 0 # old\n").unwrap();
+    let key = |start, turn, piece: &str| BookKey {
+        size: (6, 5),
+        start,
+        turn,
+        piece: piece.to_string(),
+    };
+    assert_eq!(book.get(&key((4, 4), 1, "OO")), Some((3, 3)));
+    assert_eq!(book.get(&key((1, 1), 2, "O.")), Some((0, 0)));
+    assert_eq!(
+        OpeningBook::parse(&book.to_string()).unwrap().entries,
+        book.entries
+    );
+}
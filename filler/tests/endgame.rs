@@ -0,0 +1,58 @@
+use filler::{Evaluator, State};
+
+/// A turn for p1 on a 6 x 4 board where the opponent owns `opponent`
+/// cells of its bottom row and we own the two top cells of the left
+/// column.
+fn turn(opponent: usize) -> Vec<String> {
+    let bottom: String = (0..6)
+        .map(|x| if x < opponent { '$' } else { '.' })
+        .collect();
+    format!(
+        "$$$ exec p1 : [robots/filler]\n\
+         Anfield 6 4:\n    012345\n000 @.....\n001 @.....\n002 ......\n003 {bottom}\n\
+         Piece 2 1:\nOO\n"
+    )
+    .lines()
+    .map(String::from)
+    .collect()
+}
+
+#[test]
+fn switches_to_fill_after_three_turns_without_opponent_growth() {
+    let mut state = State::new();
+    for (opponent, evaluator) in [
+        (1, Evaluator::Heuristic),
+        (2, Evaluator::Heuristic),
+        (2, Evaluator::Heuristic),
+        (2, Evaluator::Fill),
+        // Once stuck, always stuck.
+        (3, Evaluator::Fill),
+    ] {
+        state.parse(turn(opponent)).unwrap();
+        assert_eq!(state.evaluator(), evaluator, "turn {}", state.turn);
+    }
+}
+
+#[test]
+fn growing_opponent_is_never_stuck() {
+    let mut state = State::new();
+    for opponent in 1..=6 {
+        state.parse(turn(opponent)).unwrap();
+        assert_eq!(state.evaluator(), Evaluator::Heuristic);
+    }
+}
+
+#[test]
+fn fill_evaluator_hugs_walls_and_cells() {
+    let mut state = State::new();
+    for _ in 0..3 {
+        state.parse(turn(2)).unwrap();
+    }
+    assert_eq!(state.evaluator(), Evaluator::Fill);
+    let ranked = state.ranked_positions();
+    let names: Vec<_> = ranked[0].1.terms.iter().map(|t| t.name).collect();
+    assert_eq!(names, ["snug", "holes"]);
+    // Along the top wall beats the row below, open to the bottom.
+    let origins: Vec<_> = ranked.iter().map(|(p, _)| (p.x, p.y)).collect();
+    assert_eq!(origins, [(0, 0), (0, 1)]);
+}
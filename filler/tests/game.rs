@@ -0,0 +1,96 @@
+use filler::{
+    game::{Game, GameError, Move},
+    Anfield, Piece, State,
+};
+
+/// A 5 x 4 board with player 1 on its left edge and player 2 opposite.
+fn game() -> Game {
+    let mut anfield = Anfield::new(5, 4);
+    for y in 0..4 {
+        for x in 0..5 {
+            anfield.claim((x, y), 0);
+        }
+    }
+    anfield.claim((0, 1), 1);
+    anfield.claim((4, 2), 2);
+    Game::new(anfield, 2)
+}
+
+fn piece(rows: &[&str]) -> Piece {
+    Piece::new(rows.iter().map(|r| r.chars().collect()).collect())
+}
+
+#[test]
+fn rejects_cells_left_of_the_board() {
+    let mut game = game();
+    let mv = Move::Place {
+        player: 1,
+        piece: piece(&["OO"]),
+        origin: (-1, 1),
+    };
+    assert_eq!(game.apply(mv.clone()), Err(GameError::IllegalMove(mv)));
+    assert_eq!(game.scores(), vec![(1, 1), (2, 1)]);
+}
+
+#[test]
+fn rejects_cells_above_the_board() {
+    let mut game = game();
+    let bar = piece(&["O", "O"]);
+    let mv = Move::Place {
+        player: 1,
+        piece: bar.clone(),
+        origin: (0, -1),
+    };
+    assert_eq!(game.apply(mv.clone()), Err(GameError::IllegalMove(mv)));
+    let mv = Move::Place {
+        player: 1,
+        piece: bar,
+        origin: (0, 0),
+    };
+    assert_eq!(game.apply(mv), Ok(()));
+}
+
+#[test]
+fn padded_pieces_have_negative_origins() {
+    let mut game = game();
+    let padded = piece(&["..", ".O", ".O"]);
+    let moves = game.legal_moves(1, &padded);
+    let origins: Vec<(i32, i32)> = moves
+        .iter()
+        .map(|m| match m {
+            Move::Place { origin, .. } => *origin,
+            Move::Pass { .. } => unreachable!(),
+        })
+        .collect();
+    assert_eq!(origins, vec![(-1, -1), (-1, 0)]);
+    game.apply(moves[0].clone()).unwrap();
+    assert_eq!(game.scores(), vec![(1, 2), (2, 1)]);
+}
+
+#[test]
+fn bot_finds_moves_that_only_fit_at_negative_origins() {
+    let text = "\
+$$$ exec p1 : [robots/filler]
+Anfield 5 4:
+    01234
+000 @....
+001 .....
+002 .....
+003 ....$
+Piece 3 3:
+...
+.O.
+.OO
+";
+    let mut state = State::new();
+    state
+        .parse(text.lines().map(String::from).collect())
+        .unwrap();
+    let robot = &state.robot;
+    let piece = &state.current_piece;
+    assert!(state.anfield.has_legal_move(piece, robot));
+    let positions = state.anfield.potential_positions(piece, robot);
+    let origins: Vec<_> = positions.keys().map(|p| (p.x, p.y)).collect();
+    assert_eq!(origins, vec![(-1, -1)]);
+    assert_eq!(state.choose_move(|_| {}), (-1, -1));
+}
@@ -0,0 +1,17 @@
+use filler::logger::{Config, Level};
+
+#[test]
+fn debug_shows_scoring_traces() {
+    let mut config = Config::default();
+    config.enable_debug();
+    assert_eq!(config.level_for("filler::process"), Level::Trace);
+    assert_eq!(config.level_for("filler::protocol"), Level::Debug);
+}
+
+#[test]
+fn later_filters_override_debug() {
+    let mut config = Config::default();
+    config.enable_debug();
+    config.apply_filter("filler::process=info").unwrap();
+    assert_eq!(config.level_for("filler::process"), Level::Info);
+}
@@ -0,0 +1,84 @@
+use filler::{
+    protocol::{Turn, Validation},
+    PlayerRegistry, State,
+};
+
+fn lines(text: &str) -> Vec<String> {
+    text.lines().map(String::from).collect()
+}
+
+#[test]
+fn three_player_board_parses() {
+    let text = "\
+$$$ exec p1 : [robots/bender]
+$$$ exec p2 : [robots/filler]
+$$$ exec p3 : [robots/terminator]
+Anfield 6 3:
+    012345
+000 @....%
+001 ......
+002 ..$$..
+Piece 1 1:
+O
+";
+    let mut state = State::new();
+    state.player_override = Some(2);
+    state.parse(lines(text)).unwrap();
+    assert_eq!(state.robot.id, 2);
+    assert_eq!(state.robot.characters, ['s', '$']);
+    assert_eq!(state.robot.score, 2);
+    let opponents: Vec<_> = state
+        .opponents
+        .iter()
+        .map(|o| (o.id, o.characters, o.starting_point, o.score))
+        .collect();
+    assert_eq!(
+        opponents,
+        [(1, ['a', '@'], (0, 0), 1), (3, ['d', '%'], (5, 0), 1)]
+    );
+    assert_eq!(state.opponent_history, [2]);
+}
+
+#[test]
+fn four_player_symbols_are_the_default() {
+    let players = PlayerRegistry::default();
+    assert_eq!(players.len(), 4);
+    assert_eq!(players.owner('&'), Some(4));
+    assert_eq!(players.owner('f'), Some(4));
+    assert_eq!(players.symbols(4), Some(['f', '&']));
+    assert_eq!(players.owner('.'), None);
+}
+
+#[test]
+fn custom_symbols_parse_and_read_boards() {
+    let players = PlayerRegistry::parse("xX, oO,+#").unwrap();
+    assert_eq!(players.to_string(), "xX,oO,+#");
+    assert_eq!(players.ids().collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(players.robot(3).characters, ['+', '#']);
+
+    let text = "\
+Anfield 4 2:
+    0123
+000 Xo..
+001 ..#+
+Piece 1 1:
+O
+";
+    let turn = Turn::parse(&lines(text), &players, Validation::Strict).unwrap();
+    let owner = |coord| turn.anfield.occupation.get(&coord).copied();
+    assert_eq!(owner((0, 0)), Some(1));
+    assert_eq!(owner((1, 0)), Some(2));
+    assert_eq!(owner((2, 1)), Some(3));
+    assert_eq!(owner((3, 1)), Some(3));
+    assert!(turn.anfield.recent.contains(&(1, 0)));
+    // The default symbols mean nothing here.
+    let text = text.replace('X', "@");
+    assert!(Turn::parse(&lines(&text), &players, Validation::Strict).is_err());
+}
+
+#[test]
+fn invalid_symbol_sets_are_rejected() {
+    for spec in ["xX,x+", "xX,o", "xX,..", "abc"] {
+        assert!(PlayerRegistry::parse(spec).is_err(), "{spec}");
+    }
+}
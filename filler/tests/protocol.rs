@@ -0,0 +1,141 @@
+use filler::{
+    dialect::Dialect,
+    protocol::{ExecLine, Turn, Validation},
+    Anfield, Piece, PlayerRegistry, Robot, State,
+};
+
+fn piece(rows: &[&str]) -> Piece {
+    Piece::new(rows.iter().map(|r| r.chars().collect()).collect())
+}
+
+/// A board with every cell claimed, some of them as last placed.
+fn anfield() -> Anfield {
+    let mut anfield = Anfield::new(7, 5);
+    for y in 0..5 {
+        for x in 0..7 {
+            anfield.claim((x, y), 0);
+        }
+    }
+    for (coord, id) in [((1, 1), 1), ((2, 1), 1), ((5, 3), 2), ((5, 4), 2)] {
+        anfield.claim(coord, id);
+    }
+    anfield.recent.insert((2, 1));
+    anfield.recent.insert((5, 4));
+    anfield
+}
+
+fn round_trip(turn: &Turn) -> Turn {
+    let players = turn.dialect.players();
+    let text = turn.display(&players).to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    Turn::parse(&lines, &players, Validation::Strict).unwrap()
+}
+
+#[test]
+fn anfield_turn_round_trips() {
+    let turn = Turn {
+        dialect: Dialect::Anfield,
+        execs: vec![ExecLine {
+            player: 2,
+            path: "robots/bender".to_string(),
+        }],
+        anfield: anfield(),
+        piece: piece(&[".O.", "OO."]),
+    };
+    assert_eq!(round_trip(&turn), turn);
+}
+
+#[test]
+fn plateau_turn_round_trips() {
+    let turn = Turn {
+        dialect: Dialect::Plateau,
+        execs: vec![ExecLine {
+            player: 1,
+            path: "players/carli.filler".to_string(),
+        }],
+        anfield: anfield(),
+        piece: piece(&["O.", "OO", ".O"]),
+    };
+    let text = turn.display(&Dialect::Plateau.players()).to_string();
+    assert!(text.contains("Plateau 5 7:\n"));
+    assert!(text.contains("Piece 3 2:\n*.\n**\n.*\n"));
+    assert_eq!(round_trip(&turn), turn);
+}
+
+#[test]
+fn piece_round_trips() {
+    let turn = Turn {
+        piece: piece(&["..O", "OOO"]),
+        ..Turn::default()
+    };
+    assert_eq!(
+        turn.display(&Dialect::Anfield.players()).to_string(),
+        "Piece 3 2:\n..O\nOOO\n"
+    );
+    assert_eq!(round_trip(&turn), turn);
+    for dialect in Dialect::ALL {
+        let turn = Turn {
+            dialect,
+            anfield: Anfield::new(1, 1),
+            piece: piece(&["O.", ".O", "OO"]),
+            ..Turn::default()
+        };
+        assert_eq!(round_trip(&turn).piece, turn.piece);
+    }
+}
+
+#[test]
+fn exec_lines_round_trip() {
+    for line in ["$$$ exec p1 : [robots/filler]", "$$$ exec p12 : [a b/c]"] {
+        let exec = ExecLine::parse(line).unwrap();
+        assert_eq!(exec.to_string(), line);
+        assert_eq!(ExecLine::parse(&exec.to_string()), Some(exec));
+    }
+}
+
+#[test]
+fn caches_do_not_break_equality() {
+    let turn = Turn {
+        anfield: anfield(),
+        piece: piece(&["O"]),
+        ..Turn::default()
+    };
+    let mut cached = turn.clone();
+    let mut robot = Robot::new(1, ['a', '@']);
+    robot.weights.frontier_min_free = 0.0;
+    cached.anfield.update_opp_occupation(&robot);
+    assert!(!cached.anfield.opp_occupation.is_empty());
+    assert_eq!(round_trip(&cached), turn);
+}
+
+#[test]
+fn given_symbols_win_over_the_dialect() {
+    let turn = Turn {
+        dialect: Dialect::Plateau,
+        execs: vec![ExecLine {
+            player: 1,
+            path: "players/carli.filler".to_string(),
+        }],
+        anfield: anfield(),
+        piece: piece(&["O"]),
+    };
+    for players in [Dialect::Anfield.players(), Dialect::Plateau.players()] {
+        let text = turn.display(&players).to_string();
+        let mut state = State::new();
+        state.players = players.clone();
+        state.symbols_given = true;
+        state
+            .parse(text.lines().map(String::from).collect())
+            .unwrap();
+        assert_eq!(state.players, players);
+        assert_eq!(state.anfield, turn.anfield);
+    }
+
+    let mut state = State::new();
+    let text = turn.display(&Dialect::Plateau.players()).to_string();
+    state
+        .parse(text.lines().map(String::from).collect())
+        .unwrap();
+    assert_eq!(state.players, Dialect::Plateau.players());
+    assert_ne!(state.players, PlayerRegistry::default());
+}
@@ -0,0 +1,49 @@
+use filler::{candidates_json, State, Weights};
+
+/// p1 alone in the middle of a 7 x 5 board, offered a single cell.
+const TURN: &str = "\
+$$$ exec p1 : [robots/filler]
+Anfield 7 5:
+    0123456
+000 .......
+001 .......
+002 ...@...
+003 .......
+004 ......$
+Piece 1 1:
+O
+";
+
+fn state() -> State {
+    let mut state = State::new();
+    state
+        .parse(TURN.lines().map(String::from).collect())
+        .unwrap();
+    state
+}
+
+#[test]
+fn edge_proximity_reports_distance_and_weight() {
+    let state = state();
+    let ranked = state.ranked_positions();
+    let (position, breakdown) = &ranked[0];
+    assert_eq!((position.x, position.y), (3, 2));
+    let edge = breakdown.term("edge_proximity").unwrap();
+    assert_eq!(edge.value, 2.0);
+    assert_eq!(edge.weight, Weights::default().edge_proximity);
+    // 20 * 2 / 7, in integers.
+    assert_eq!(edge.contribution(), 5.0);
+    let sum: f32 = breakdown.terms.iter().map(|t| t.contribution()).sum();
+    assert_eq!(breakdown.total, sum);
+}
+
+#[test]
+fn dump_shows_each_term_with_its_weight() {
+    let state = state();
+    let json = candidates_json(state.turn, state.evaluator(), &state.ranked_positions());
+    assert!(
+        json.contains(r#"{"name":"edge_proximity","value":2,"weight":20,"contribution":5}"#),
+        "{json}"
+    );
+    assert!(json.contains(r#""name":"blocking","#), "{json}");
+}
@@ -0,0 +1,14 @@
+use filler::{
+    selfplay::{play, Rng},
+    Weights,
+};
+
+#[test]
+fn self_play_is_reproducible() {
+    let weights = [Weights::default(); 2];
+    let a = play(12, 10, [(2, 2), (9, 7)], weights, &mut Rng::new(5));
+    let b = play(12, 10, [(2, 2), (9, 7)], weights, &mut Rng::new(5));
+    assert_eq!(a, b);
+    assert!(a.moves.iter().any(|m| m.player == 1));
+    assert!(a.scores.iter().all(|&s| s > 1));
+}
@@ -0,0 +1,119 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use filler::{
+    session::{self, Recorder},
+    State,
+};
+
+/// Two players, neither named after the test binary, so only a recorded
+/// identity tells who we are.
+const TURNS: [&str; 2] = [
+    "\
+$$$ exec p1 : [robots/bender]
+$$$ exec p2 : [robots/filler]
+Anfield 6 4:
+    012345
+000 @.....
+001 ......
+002 ......
+003 .....$
+Piece 2 1:
+OO
+",
+    "\
+Anfield 6 4:
+    012345
+000 @.....
+001 ......
+002 ....$$
+003 .....$
+Piece 1 2:
+O
+O
+",
+];
+
+fn log_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("filler-{}-{name}.log", process::id()))
+}
+
+/// Plays `TURNS` as p2 the way the bot does, recording to `path`.
+fn record(path: &Path) {
+    let mut recorder = Recorder::create(path).unwrap();
+    let mut state = State::new();
+    state.player_override = Some(2);
+    for turn in TURNS {
+        let lines: Vec<String> = turn.lines().map(String::from).collect();
+        for line in &lines {
+            recorder.input(line).unwrap();
+        }
+        let first = !state.started;
+        state.parse(lines).unwrap();
+        if first {
+            recorder.identity(state.robot.id, state.dialect).unwrap();
+        }
+        let (x, y) = state.choose_move(|_| {});
+        recorder.output(&state.dialect.answer(x, y)).unwrap();
+    }
+}
+
+#[test]
+fn replay_of_a_recording_changes_nothing() {
+    let path = log_path("same");
+    record(&path);
+    let log = fs::read_to_string(&path).unwrap();
+    assert!(log.contains("\n# player p2\n# dialect anfield\n"), "{log}");
+    let replay = session::replay(&path, State::new()).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(replay.turns, 2);
+    assert!(replay.changes.is_empty());
+    assert!(replay
+        .to_string()
+        .ends_with("2 turns replayed, 0 changed\n"));
+}
+
+#[test]
+fn edited_answer_is_reported() {
+    let path = log_path("edited");
+    record(&path);
+    let log = fs::read_to_string(&path).unwrap();
+    let last = log.lines().rfind(|l| l.contains(" > ")).unwrap();
+    let (ms, _) = last.split_once(' ').unwrap();
+    fs::write(&path, log.replace(last, &format!("{ms} > 5 3"))).unwrap();
+    let replay = session::replay(&path, State::new()).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(replay.changes.len(), 1);
+    assert_eq!(replay.changes[0].turn, 2);
+    assert_eq!(replay.changes[0].recorded, "5 3");
+    assert!(replay
+        .to_string()
+        .ends_with("2 turns replayed, 1 changed\n"));
+}
+
+#[test]
+fn headers_set_player_and_check_dialect() {
+    let path = log_path("headers");
+    record(&path);
+    let log = fs::read_to_string(&path).unwrap();
+
+    // Without the recorded player, two exec lines are ambiguous.
+    fs::write(&path, log.replace("# player p2\n", "")).unwrap();
+    let err = session::replay(&path, State::new()).err().unwrap();
+    assert!(err.contains("could not tell which"), "{err}");
+
+    // --player still wins over the log.
+    fs::write(&path, &log).unwrap();
+    let mut state = State::new();
+    state.player_override = Some(1);
+    let replay = session::replay(&path, state).unwrap();
+    assert!(!replay.changes.is_empty());
+
+    fs::write(&path, log.replace("# dialect anfield", "# dialect plateau")).unwrap();
+    let err = session::replay(&path, State::new()).err().unwrap();
+    assert!(err.contains("recorded with the plateau dialect"), "{err}");
+    fs::remove_file(&path).unwrap();
+}
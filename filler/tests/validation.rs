@@ -0,0 +1,149 @@
+use filler::{
+    protocol::{Block, ParseError, Turn, Validation},
+    PlayerRegistry,
+};
+
+const GOOD: &str = "\
+Anfield 4 3:
+    0123
+000 ....
+001 .@..
+002 ...$
+Piece 2 2:
+.O
+OO
+";
+
+fn parse(text: &str, validation: Validation) -> Result<Turn, ParseError> {
+    let lines: Vec<&str> = text.lines().collect();
+    Turn::parse(&lines, &PlayerRegistry::default(), validation)
+}
+
+/// `GOOD` with `from` replaced by `to`: an error in strict mode, and in
+/// lenient mode the same turn as `recovered`.
+fn check(from: &str, to: &str, err: ParseError, recovered: &str) {
+    assert!(GOOD.contains(from), "{from}");
+    let bad = GOOD.replacen(from, to, 1);
+    assert_eq!(parse(&bad, Validation::Strict), Err(err));
+    let expected = parse(recovered, Validation::Strict).unwrap();
+    assert_eq!(parse(&bad, Validation::Lenient), Ok(expected));
+}
+
+#[test]
+fn good_turn_parses_in_both_modes() {
+    let strict = parse(GOOD, Validation::Strict).unwrap();
+    assert_eq!(parse(GOOD, Validation::Lenient).unwrap(), strict);
+    assert_eq!(strict.anfield.score(1), 1);
+}
+
+#[test]
+fn missing_board_row() {
+    check(
+        "002 ...$\n",
+        "",
+        ParseError::RowCount {
+            block: Block::Anfield,
+            expected: 3,
+            found: 2,
+        },
+        &GOOD.replacen("002 ...$", "002 ....", 1),
+    );
+}
+
+#[test]
+fn short_board_row() {
+    check(
+        "001 .@..",
+        "001 .@",
+        ParseError::RowLength {
+            block: Block::Anfield,
+            row: 1,
+            expected: 4,
+            found: 2,
+        },
+        GOOD,
+    );
+}
+
+#[test]
+fn misnumbered_board_row() {
+    check(
+        "001 .@..",
+        "007 .@..",
+        ParseError::RowNumber {
+            row: 1,
+            line: "007 .@..".to_string(),
+        },
+        GOOD,
+    );
+}
+
+#[test]
+fn unknown_board_symbol() {
+    check(
+        "001 .@..",
+        "001 .@#.",
+        ParseError::UnknownSymbol {
+            block: Block::Anfield,
+            symbol: '#',
+            x: 2,
+            y: 1,
+        },
+        GOOD,
+    );
+}
+
+#[test]
+fn missing_piece_row() {
+    check(
+        "Piece 2 2:",
+        "Piece 2 3:",
+        ParseError::RowCount {
+            block: Block::Piece,
+            expected: 3,
+            found: 2,
+        },
+        &GOOD.replacen("Piece 2 2:\n.O\nOO\n", "Piece 2 3:\n.O\nOO\n..\n", 1),
+    );
+}
+
+#[test]
+fn long_piece_row() {
+    check(
+        "\nOO\n",
+        "\nOOO\n",
+        ParseError::RowLength {
+            block: Block::Piece,
+            row: 1,
+            expected: 2,
+            found: 3,
+        },
+        GOOD,
+    );
+}
+
+#[test]
+fn unknown_piece_symbol() {
+    check(
+        "\n.O\n",
+        "\n*O\n",
+        ParseError::UnknownSymbol {
+            block: Block::Piece,
+            symbol: '*',
+            x: 0,
+            y: 0,
+        },
+        GOOD,
+    );
+}
+
+#[test]
+fn bad_header_fails_in_both_modes() {
+    let bad = GOOD.replacen("Anfield 4 3:", "Anfield 4:", 1);
+    for validation in [Validation::Strict, Validation::Lenient] {
+        assert_eq!(
+            parse(&bad, validation),
+            Err(ParseError::InvalidHeader("Anfield 4:".to_string()))
+        );
+    }
+}
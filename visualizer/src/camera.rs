@@ -0,0 +1,143 @@
+//! Zoom and pan of the board, applied on top of the fitted layout.
+
+use filler::Anfield;
+use ggez::glam::Vec2;
+use ggez::graphics::{DrawMode, MeshBuilder, Rect};
+
+use crate::Theme;
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 40.0;
+/// Longest side of the minimap.
+const MINIMAP_SIZE: f32 = 160.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub zoom: f32,
+    /// Offset of the board from its fitted position, in pixels.
+    pub pan: Vec2,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+        }
+    }
+}
+
+impl Camera {
+    /// Back to the board fitted in the window.
+    pub fn fit(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_fitted(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Where the fitted `board` ends up on screen.
+    pub fn transform(&self, board: Rect) -> Rect {
+        Rect::new(
+            board.x + self.pan.x,
+            board.y + self.pan.y,
+            board.w * self.zoom,
+            board.h * self.zoom,
+        )
+    }
+
+    /// Zooms by `factor`, keeping the board point under `at` in place.
+    pub fn zoom_at(&mut self, factor: f32, at: Vec2, board: Rect) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let origin = Vec2::new(board.x, board.y);
+        let from_origin = at - origin - self.pan;
+        self.pan = at - origin - from_origin * (zoom / self.zoom);
+        self.zoom = zoom;
+    }
+
+    pub fn pan_by(&mut self, delta: Vec2) {
+        self.pan += delta;
+    }
+}
+
+/// The whole board scaled into the bottom-right corner of `view`, with the
+/// part of it currently on screen outlined.
+pub fn minimap(anfield: &Anfield, theme: &Theme, view: Rect, board: Rect) -> Option<MeshBuilder> {
+    if anfield.width <= 0 || anfield.height <= 0 || board.w <= 0.0 {
+        return None;
+    }
+    let cell = MINIMAP_SIZE / anfield.width.max(anfield.height) as f32;
+    let (w, h) = (anfield.width as f32 * cell, anfield.height as f32 * cell);
+    let area = Rect::new(view.right() - w - 10.0, view.bottom() - h - 10.0, w, h);
+
+    let mut mesh_builder = MeshBuilder::new();
+    let _ = mesh_builder.rectangle(DrawMode::fill(), area, theme.background);
+    for (&(x, y), &id) in &anfield.occupation {
+        if id != 0 {
+            let rect = Rect::new(
+                area.x + x as f32 * cell,
+                area.y + y as f32 * cell,
+                cell,
+                cell,
+            );
+            let _ = mesh_builder.rectangle(DrawMode::fill(), rect, theme.player(id));
+        }
+    }
+    let _ = mesh_builder.rectangle(DrawMode::stroke(1.0), area, theme.grid);
+
+    // The visible part of the board, in minimap coordinates.
+    let scale = w / board.w;
+    let left = ((view.x - board.x) * scale).max(0.0);
+    let top = ((view.y - board.y) * scale).max(0.0);
+    let right = ((view.right() - board.x) * scale).min(w);
+    let bottom = ((view.bottom() - board.y) * scale).min(h);
+    if right > left && bottom > top {
+        let visible = Rect::new(area.x + left, area.y + top, right - left, bottom - top);
+        let _ = mesh_builder.rectangle(DrawMode::stroke(1.5), visible, theme.highlight);
+    }
+    Some(mesh_builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Screen position of the board point at `fraction` of `board`.
+    fn on_screen(camera: &Camera, board: Rect, fraction: Vec2) -> Vec2 {
+        let shown = camera.transform(board);
+        Vec2::new(shown.x, shown.y) + fraction * Vec2::new(shown.w, shown.h)
+    }
+
+    #[test]
+    fn zoom_keeps_the_point_under_the_cursor() {
+        let board = Rect::new(20.0, 30.0, 400.0, 200.0);
+        let mut camera = Camera::default();
+        let at = Vec2::new(120.0, 80.0);
+        let fraction = Vec2::new(0.25, 0.25);
+        assert_eq!(on_screen(&camera, board, fraction), at);
+
+        camera.zoom_at(2.0, at, board);
+        assert_eq!(camera.zoom, 2.0);
+        assert_eq!(on_screen(&camera, board, fraction), at);
+
+        camera.pan_by(Vec2::new(-15.0, 5.0));
+        let at = on_screen(&camera, board, fraction);
+        camera.zoom_at(1.5, at, board);
+        assert!((on_screen(&camera, board, fraction) - at).length() < 1e-3);
+    }
+
+    #[test]
+    fn zoom_is_clamped_and_fit_resets() {
+        let board = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let mut camera = Camera::default();
+        camera.zoom_at(1000.0, Vec2::new(50.0, 50.0), board);
+        assert_eq!(camera.zoom, MAX_ZOOM);
+        camera.zoom_at(1e-6, Vec2::new(50.0, 50.0), board);
+        assert_eq!(camera.zoom, MIN_ZOOM);
+        assert!(!camera.is_fitted());
+        camera.fit();
+        assert!(camera.is_fitted());
+        assert_eq!(camera.transform(board), board);
+    }
+}
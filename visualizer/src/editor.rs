@@ -0,0 +1,136 @@
+//! Edit mode: build a position by hand and save it as a snapshot that
+//! `filler::snapshot::load` reads back.
+
+use std::path::PathBuf;
+
+use filler::{snapshot, Anfield, Piece, PlayerRegistry};
+
+/// Players a board cell cycles through when clicked.
+const EDIT_PLAYERS: i32 = 2;
+
+pub struct Editor {
+    pub path: PathBuf,
+    /// Outcome of the last save, shown in the panel.
+    pub status: String,
+}
+
+impl Editor {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            status: String::new(),
+        }
+    }
+
+    /// An empty board with the two players in opposite corners and a
+    /// single-cell piece.
+    pub fn blank() -> (Anfield, Piece) {
+        let mut anfield = resized(&Anfield::default(), 20, 15);
+        anfield.claim((2, 2), 1);
+        anfield.claim((17, 12), 2);
+        (anfield, Piece::new(vec![vec!['O']]))
+    }
+
+    /// Empty, p1, p2, empty, ...
+    pub fn cycle_cell(anfield: &mut Anfield, coord: (i32, i32)) {
+        let id = anfield.occupation.get(&coord).copied().unwrap_or(0);
+        anfield.claim(coord, (id + 1) % (EDIT_PLAYERS + 1));
+    }
+
+    pub fn toggle_piece_cell(piece: &mut Piece, (x, y): (i32, i32)) {
+        if let Some(c) = piece
+            .cells
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+        {
+            *c = if *c == '.' { 'O' } else { '.' };
+        }
+    }
+
+    pub fn save(&mut self, anfield: &Anfield, piece: &Piece, players: &PlayerRegistry) {
+        self.status = match snapshot::save(&self.path, anfield, piece, players) {
+            Ok(()) => format!("saved {}", self.path.display()),
+            Err(err) => format!("could not save {}: {err}", self.path.display()),
+        };
+    }
+}
+
+/// A copy of `anfield` cut or padded to `width` x `height`.
+pub fn resized(anfield: &Anfield, width: i32, height: i32) -> Anfield {
+    let mut out = Anfield::new(width.max(1), height.max(1));
+    for y in 0..out.height {
+        for x in 0..out.width {
+            let id = anfield.occupation.get(&(x, y)).copied().unwrap_or(0);
+            out.claim((x, y), id);
+        }
+    }
+    out
+}
+
+/// A copy of `piece` cut or padded with `.` to `width` x `height`.
+pub fn resized_piece(piece: &Piece, width: i32, height: i32) -> Piece {
+    let (width, height) = (width.max(1) as usize, height.max(1) as usize);
+    let cells = (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    piece
+                        .cells
+                        .get(y)
+                        .and_then(|row| row.get(x))
+                        .copied()
+                        .unwrap_or('.')
+                })
+                .collect()
+        })
+        .collect();
+    Piece::new(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resizing_keeps_cells_inside_and_clears_the_rest() {
+        let (anfield, _) = Editor::blank();
+        let small = resized(&anfield, 10, 5);
+        assert_eq!((small.width, small.height), (10, 5));
+        assert_eq!(small.occupation.len(), 50);
+        assert_eq!(small.score(1), 1);
+        assert_eq!(small.score(2), 0);
+
+        let big = resized(&small, 12, 8);
+        assert_eq!(big.occupation.len(), 96);
+        assert_eq!(big.occupation.get(&(2, 2)), Some(&1));
+        assert_eq!(big.occupation.get(&(11, 7)), Some(&0));
+        assert_eq!(resized(&big, 0, -3).occupation.len(), 1);
+    }
+
+    #[test]
+    fn resizing_a_piece_pads_with_dots() {
+        let piece = Piece::new(vec![vec!['O', '.'], vec!['O', 'O']]);
+        let wide = resized_piece(&piece, 3, 1);
+        assert_eq!(wide.cells, [['O', '.', '.']]);
+        assert_eq!((wide.width, wide.height), (3, 1));
+        let tall = resized_piece(&piece, 2, 3);
+        assert_eq!(tall.cells, [['O', '.'], ['O', 'O'], ['.', '.']]);
+        assert_eq!(resized_piece(&piece, 0, 0).cells, [['O']]);
+    }
+
+    #[test]
+    fn cells_cycle_through_both_players() {
+        let (mut anfield, mut piece) = Editor::blank();
+        let owners: Vec<_> = (0..3)
+            .map(|_| {
+                Editor::cycle_cell(&mut anfield, (5, 5));
+                anfield.occupation[&(5, 5)]
+            })
+            .collect();
+        assert_eq!(owners, [1, 2, 0]);
+        Editor::toggle_piece_cell(&mut piece, (0, 0));
+        assert_eq!(piece.cells, [['.']]);
+        Editor::toggle_piece_cell(&mut piece, (4, 0));
+        assert_eq!(piece.cells, [['.']]);
+    }
+}
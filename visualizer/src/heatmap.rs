@@ -0,0 +1,144 @@
+use filler::{Anfield, Piece, Position, Robot, ScoreBreakdown};
+use ggez::graphics::{Color, DrawMode, MeshBuilder};
+
+use crate::Grid;
+
+/// Colours every legal origin of the current piece by its evaluation, at
+/// the cell its first filled cell lands on.
+#[derive(Default)]
+pub struct Heatmap {
+    pub enabled: bool,
+    /// Term to display, or the total score when `None`.
+    pub term: Option<&'static str>,
+    candidates: Vec<(Position, ScoreBreakdown)>,
+}
+
+impl Heatmap {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Evaluates every legal origin of `piece` for `robot`.
+    pub fn update(&mut self, anfield: &Anfield, piece: &Piece, robot: &Robot) {
+        self.candidates.clear();
+        if robot.id == 0 || piece.height == 0 {
+            return;
+        }
+        let mut anfield = anfield.clone();
+        anfield.update_opp_occupation(robot);
+        self.candidates = anfield
+            .potential_positions(piece, robot)
+            .into_iter()
+            .collect();
+    }
+
+    /// Cycles through the total and each term of the evaluation.
+    pub fn next_term(&mut self) {
+        let names: Vec<&'static str> = self
+            .candidates
+            .first()
+            .map(|(_, b)| b.terms.iter().map(|t| t.name).collect())
+            .unwrap_or_default();
+        let next = match self.term {
+            None => 0,
+            Some(term) => names.iter().position(|&n| n == term).map_or(0, |i| i + 1),
+        };
+        self.term = names.get(next).copied();
+    }
+
+    fn value(&self, breakdown: &ScoreBreakdown) -> f32 {
+        match self.term {
+            None => breakdown.total,
+            Some(name) => breakdown.term(name).map_or(0.0, |t| t.contribution()),
+        }
+    }
+
+    fn range(&self) -> Option<(f32, f32)> {
+        let values = self.candidates.iter().map(|(_, b)| self.value(b));
+        let min = values.clone().reduce(f32::min)?;
+        let max = values.reduce(f32::max)?;
+        Some((min, max))
+    }
+
+    pub fn label(&self) -> String {
+        let term = self.term.unwrap_or("total");
+        match self.range() {
+            Some((min, max)) => format!("heatmap: {term} [{min:.1} .. {max:.1}]"),
+            None => format!("heatmap: {term} (no legal move)"),
+        }
+    }
+
+    pub fn build(&self, grid: &Grid) -> Option<MeshBuilder> {
+        if !self.enabled {
+            return None;
+        }
+        let (min, max) = self.range()?;
+        let mut mesh_builder = MeshBuilder::new();
+        for (p, breakdown) in &self.candidates {
+            let t = if max > min {
+                (self.value(breakdown) - min) / (max - min)
+            } else {
+                1.0
+            };
+            let (dx, dy) = p.piece.anchor();
+            let rect = grid.cell_rect(p.x + dx, p.y + dy);
+            let _ = mesh_builder.rectangle(DrawMode::fill(), rect, gradient(t));
+        }
+        Some(mesh_builder)
+    }
+}
+
+/// Blue for the worst candidate, red for the best.
+fn gradient(t: f32) -> Color {
+    Color::new(t, 0.2, 1.0 - t, 0.7)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heatmap() -> Heatmap {
+        let mut anfield = Anfield::new(7, 5);
+        anfield.claim((3, 2), 1);
+        anfield.claim((6, 4), 2);
+        let mut heatmap = Heatmap::default();
+        let piece = Piece::new(vec![vec!['O', 'O']]);
+        heatmap.update(&anfield, &piece, &Robot::new(1, ['a', '@']));
+        heatmap
+    }
+
+    #[test]
+    fn terms_cycle_back_to_the_total() {
+        let mut heatmap = heatmap();
+        assert_eq!(heatmap.candidates.len(), 2);
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            heatmap.next_term();
+            seen.push(heatmap.term);
+        }
+        assert_eq!(
+            seen,
+            [
+                Some("blocking"),
+                Some("edge_proximity"),
+                Some("surround"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn label_shows_the_range_of_the_term() {
+        let mut heatmap = heatmap();
+        let totals: Vec<f32> = heatmap.candidates.iter().map(|(_, b)| b.total).collect();
+        let (min, max) = heatmap.range().unwrap();
+        assert_eq!(min, totals.iter().copied().fold(f32::MAX, f32::min));
+        assert_eq!(max, totals.iter().copied().fold(f32::MIN, f32::max));
+        assert_eq!(
+            heatmap.label(),
+            format!("heatmap: total [{min:.1} .. {max:.1}]")
+        );
+        heatmap.update(&Anfield::new(3, 3), &Piece::default(), &Robot::default());
+        assert_eq!(heatmap.label(), "heatmap: total (no legal move)");
+    }
+}
@@ -0,0 +1,79 @@
+//! Play mode: the visualizer stands in for a bot and the user places the
+//! pieces with the mouse.
+
+use std::io::{self, Write};
+
+use filler::{dialect::Dialect, Anfield, Piece, Robot};
+use ggez::graphics::Color;
+
+use crate::Theme;
+
+#[derive(Debug, Default)]
+pub struct Human {
+    /// Our player number, known once the exec line arrives.
+    pub id: i32,
+    /// A piece was offered and no answer has been sent yet.
+    pub awaiting: bool,
+    /// Board cell under the mouse, where the first filled cell of the
+    /// piece would go.
+    pub cursor: Option<(i32, i32)>,
+}
+
+impl Human {
+    /// Origin of `piece` with its first filled cell at `cell`, which for
+    /// padded pieces can be above or left of the board.
+    pub fn origin(piece: &Piece, cell: (i32, i32)) -> (i32, i32) {
+        let (x, y) = piece.anchor();
+        (cell.0 - x, cell.1 - y)
+    }
+
+    pub fn can_place(&self, anfield: &Anfield, piece: &Piece, origin: (i32, i32)) -> bool {
+        anfield.can_place(origin, &Robot::new(self.id, ['?', '?']), piece)
+    }
+
+    pub fn has_legal_move(&self, anfield: &Anfield, piece: &Piece) -> bool {
+        anfield.has_legal_move(piece, &Robot::new(self.id, ['?', '?']))
+    }
+
+    /// Colour of the piece under the cursor.
+    pub fn tint(legal: bool, theme: &Theme) -> Color {
+        if legal {
+            theme.highlight
+        } else {
+            theme.warning
+        }
+    }
+
+    /// Answers the referee in its own dialect.
+    pub fn submit(&mut self, (x, y): (i32, i32), dialect: Dialect) -> io::Result<()> {
+        self.awaiting = false;
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "{}", dialect.answer(x, y))?;
+        stdout.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn piece(rows: &[&str]) -> Piece {
+        Piece::new(rows.iter().map(|r| r.chars().collect()).collect())
+    }
+
+    #[test]
+    fn clicks_reach_origins_left_of_and_above_the_board() {
+        let mut anfield = Anfield::new(5, 4);
+        anfield.claim((0, 0), 1);
+        let human = Human {
+            id: 1,
+            ..Human::default()
+        };
+        let padded = piece(&["...", ".O.", ".OO"]);
+        let origin = Human::origin(&padded, (0, 0));
+        assert_eq!(origin, (-1, -1));
+        assert!(human.can_place(&anfield, &padded, origin));
+        assert!(human.has_legal_move(&anfield, &padded));
+        assert_eq!(Human::origin(&piece(&["OO"]), (2, 3)), (2, 3));
+    }
+}
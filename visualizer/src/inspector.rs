@@ -0,0 +1,147 @@
+//! Details of a single cell: shown on hover, kept on screen when pinned.
+
+use std::collections::HashMap;
+
+use filler::{Anfield, Cell, Weights};
+
+#[derive(Debug, Default)]
+pub struct Inspector {
+    pub hover: Option<(i32, i32)>,
+    pub pinned: Option<(i32, i32)>,
+    /// Board number each cell was first seen owned on.
+    pub claimed: HashMap<(i32, i32), usize>,
+}
+
+impl Inspector {
+    /// Notes the cells owned on board number `turn`.
+    pub fn record(&mut self, anfield: &Anfield, turn: usize) {
+        for (coord, id) in &anfield.occupation {
+            if *id != 0 {
+                self.claimed.entry(*coord).or_insert(turn);
+            }
+        }
+    }
+
+    /// Pins `coord`, or unpins it if it already is.
+    pub fn toggle_pin(&mut self, coord: (i32, i32)) {
+        self.pinned = if self.pinned == Some(coord) {
+            None
+        } else {
+            Some(coord)
+        };
+    }
+
+    /// Coordinate, owner and claim turn, on one line.
+    pub fn summary(
+        &self,
+        anfield: &Anfield,
+        coord: (i32, i32),
+        name: impl Fn(i32) -> String,
+    ) -> String {
+        let (x, y) = coord;
+        match anfield.occupation.get(&coord).copied().unwrap_or(0) {
+            0 => format!("({x}, {y}) empty"),
+            id => match self.claimed.get(&coord) {
+                Some(turn) => format!("({x}, {y}) {} since turn {turn}", name(id)),
+                None => format!("({x}, {y}) {}", name(id)),
+            },
+        }
+    }
+
+    /// `summary`, the 3x3 neighbourhood by owner and the blocking potential
+    /// of the cell for `owner`, or for `to_move` if it is empty.
+    pub fn details(
+        &self,
+        anfield: &Anfield,
+        coord: (i32, i32),
+        to_move: i32,
+        weights: &Weights,
+        name: impl Fn(i32) -> String,
+    ) -> String {
+        let mut out = self.summary(anfield, coord, &name);
+        let owner = match anfield.occupation.get(&coord).copied().unwrap_or(0) {
+            0 => to_move,
+            id => id,
+        };
+        let cell = Cell::new(coord.0, coord.1, owner);
+        let neighbours = cell.get_neightboor(anfield);
+        for dy in -1..=1 {
+            out.push('\n');
+            for dx in -1..=1 {
+                let c = neighbours
+                    .iter()
+                    .find(|n| (n.x, n.y) == (coord.0 + dx, coord.1 + dy))
+                    .map_or(' ', |n| match n.occupied_by {
+                        0 => '.',
+                        id => char::from_digit(id as u32, 36).unwrap_or('?'),
+                    });
+                out.push(c);
+            }
+        }
+        out.push_str(&format!(
+            "\nblocking potential for {}: {}",
+            name(owner),
+            cell.blocking_potential(anfield, weights)
+        ));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(id: i32) -> String {
+        format!("p{id}")
+    }
+
+    #[test]
+    fn pins_toggle_and_move() {
+        let mut inspector = Inspector::default();
+        inspector.toggle_pin((1, 2));
+        assert_eq!(inspector.pinned, Some((1, 2)));
+        inspector.toggle_pin((3, 4));
+        assert_eq!(inspector.pinned, Some((3, 4)));
+        inspector.toggle_pin((3, 4));
+        assert_eq!(inspector.pinned, None);
+    }
+
+    #[test]
+    fn cells_remember_the_turn_they_were_claimed() {
+        let mut anfield = Anfield::new(3, 3);
+        anfield.claim((0, 0), 1);
+        let mut inspector = Inspector::default();
+        inspector.record(&anfield, 1);
+        anfield.claim((1, 1), 2);
+        inspector.record(&anfield, 2);
+        assert_eq!(
+            inspector.summary(&anfield, (0, 0), name),
+            "(0, 0) p1 since turn 1"
+        );
+        assert_eq!(
+            inspector.summary(&anfield, (1, 1), name),
+            "(1, 1) p2 since turn 2"
+        );
+        assert_eq!(inspector.summary(&anfield, (2, 2), name), "(2, 2) empty");
+        anfield.claim((2, 2), 1);
+        assert_eq!(inspector.summary(&anfield, (2, 2), name), "(2, 2) p1");
+    }
+
+    #[test]
+    fn details_show_the_neighbourhood() {
+        let mut anfield = Anfield::new(3, 3);
+        for y in 0..3 {
+            for x in 0..3 {
+                anfield.claim((x, y), 0);
+            }
+        }
+        anfield.claim((0, 0), 1);
+        anfield.claim((1, 1), 2);
+        let inspector = Inspector::default();
+        let details = inspector.details(&anfield, (0, 1), 1, &Weights::default(), name);
+        let lines: Vec<&str> = details.lines().collect();
+        assert_eq!(lines[0], "(0, 1) empty");
+        assert_eq!(&lines[1..4], [" 1.", " .2", " .."]);
+        assert!(lines[4].starts_with("blocking potential for p1: "));
+    }
+}
@@ -0,0 +1,111 @@
+//! Where the board and the side panel go in the window.
+
+use ggez::graphics::Rect;
+
+const MARGIN: f32 = 20.0;
+const PANEL_WIDTH: f32 = 260.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Layout {
+    /// Exactly `cols * cell` by `rows * cell`.
+    pub board: Rect,
+    /// Side of a (square) cell.
+    pub cell: f32,
+    /// Room right of the board for scores and other panels.
+    pub panel: Rect,
+    /// Everything left of the panel, where the board may be drawn when
+    /// zoomed in.
+    pub view: Rect,
+}
+
+impl Layout {
+    /// Largest square cells that fit `cols` x `rows` next to the panel, with
+    /// the board centred in the space left.
+    pub fn compute(window: (f32, f32), cols: usize, rows: usize) -> Self {
+        let panel_w = PANEL_WIDTH.min(window.0 / 4.0);
+        let area = Rect::new(
+            MARGIN,
+            MARGIN,
+            (window.0 - panel_w - 3.0 * MARGIN).max(0.0),
+            (window.1 - 2.0 * MARGIN).max(0.0),
+        );
+        let (cols, rows) = (cols.max(1) as f32, rows.max(1) as f32);
+        let cell = (area.w / cols).min(area.h / rows).floor().max(1.0);
+        let (w, h) = (cols * cell, rows * cell);
+        let board = Rect::new(
+            area.x + ((area.w - w) / 2.0).max(0.0).floor(),
+            area.y + ((area.h - h) / 2.0).max(0.0).floor(),
+            w,
+            h,
+        );
+        let panel = Rect::new(board.x + w + MARGIN, MARGIN, panel_w, area.h);
+        let view = Rect::new(
+            0.0,
+            0.0,
+            (panel.x - MARGIN / 2.0).max(1.0),
+            window.1.max(1.0),
+        );
+        Self {
+            board,
+            cell,
+            panel,
+            view,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_are_square_and_the_board_fits() {
+        for (window, cols, rows) in [
+            ((1200.0, 800.0), 40, 30),
+            ((1200.0, 800.0), 100, 15),
+            ((600.0, 1000.0), 17, 99),
+        ] {
+            let layout = Layout::compute(window, cols, rows);
+            assert_eq!(layout.board.w, cols as f32 * layout.cell);
+            assert_eq!(layout.board.h, rows as f32 * layout.cell);
+            assert_eq!(layout.cell, layout.cell.floor());
+            assert!(layout.board.x >= MARGIN && layout.board.y >= MARGIN);
+            assert!(layout.board.bottom() <= window.1 - MARGIN);
+            assert!(layout.board.right() + MARGIN <= layout.panel.x);
+            assert!(layout.panel.right() <= window.0 - MARGIN);
+            assert!(layout.view.contains([layout.board.x, layout.board.y]));
+        }
+    }
+
+    #[test]
+    fn board_is_centred_in_its_area() {
+        let window = (1200.0, 800.0);
+        for (cols, rows) in [(100, 10), (10, 100)] {
+            let layout = Layout::compute(window, cols, rows);
+            // The panel follows the board; the area is what is left of it.
+            let area_right = window.0 - layout.panel.w - 2.0 * MARGIN;
+            let left = layout.board.x - MARGIN;
+            let right = area_right - layout.board.right();
+            let top = layout.board.y - MARGIN;
+            let bottom = window.1 - MARGIN - layout.board.bottom();
+            assert!((left - right).abs() <= 1.0, "{cols} x {rows}");
+            assert!((top - bottom).abs() <= 1.0, "{cols} x {rows}");
+            // Cells one pixel larger would not fit.
+            let bigger = layout.cell + 1.0;
+            assert!(
+                cols as f32 * bigger > area_right - MARGIN
+                    || rows as f32 * bigger > window.1 - 2.0 * MARGIN,
+                "{cols} x {rows}"
+            );
+        }
+    }
+
+    #[test]
+    fn tiny_windows_keep_one_pixel_cells() {
+        let layout = Layout::compute((10.0, 10.0), 50, 50);
+        assert_eq!(layout.cell, 1.0);
+        let layout = Layout::compute((800.0, 600.0), 0, 0);
+        assert!(layout.cell >= 1.0);
+        assert!(layout.view.w >= 1.0 && layout.view.h >= 1.0);
+    }
+}
@@ -0,0 +1,80 @@
+use filler::{Anfield, Piece, Robot};
+use ggez::graphics::{DrawMode, MeshBuilder};
+
+use crate::{Grid, Theme};
+
+/// Outlines every origin where the player to move can place the current
+/// piece, at the cell its first filled cell lands on: the cell to click in
+/// play mode, and on the board even for origins above or left of it.
+#[derive(Default)]
+pub struct LegalMoves {
+    pub enabled: bool,
+    pub origins: Vec<(i32, i32)>,
+    /// Offset of the first filled cell of the piece.
+    anchor: (i32, i32),
+}
+
+impl LegalMoves {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Origins of `piece` that `Anfield::can_place` accepts for `robot`.
+    pub fn update(&mut self, anfield: &Anfield, piece: &Piece, robot: &Robot) {
+        self.origins.clear();
+        if robot.id == 0 || piece.height == 0 {
+            return;
+        }
+        self.anchor = piece.anchor();
+        self.origins.extend(
+            anfield
+                .origins(piece)
+                .filter(|&origin| anfield.can_place(origin, robot, piece)),
+        );
+    }
+
+    pub fn label(&self) -> String {
+        match self.origins.len() {
+            1 => "1 legal origin".to_string(),
+            n => format!("{n} legal origins"),
+        }
+    }
+
+    pub fn build(&self, grid: &Grid, theme: &Theme) -> Option<MeshBuilder> {
+        if !self.enabled || self.origins.is_empty() {
+            return None;
+        }
+        let mut mesh_builder = MeshBuilder::new();
+        let (dx, dy) = self.anchor;
+        for &(x, y) in &self.origins {
+            let _ = mesh_builder.rectangle(
+                DrawMode::stroke(2.0),
+                grid.cell_rect(x + dx, y + dy),
+                theme.highlight,
+            );
+        }
+        Some(mesh_builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_origins_left_of_and_above_the_board() {
+        let mut anfield = Anfield::new(5, 4);
+        anfield.claim((0, 0), 1);
+        anfield.claim((4, 3), 2);
+        let padded = Piece::new(vec![
+            vec!['.', '.', '.'],
+            vec!['.', 'O', '.'],
+            vec!['.', 'O', 'O'],
+        ]);
+        let mut legal = LegalMoves::default();
+        legal.update(&anfield, &padded, &Robot::new(1, ['a', '@']));
+        assert_eq!(legal.origins, vec![(-1, -1)]);
+        assert_eq!(legal.anchor, (1, 1));
+        assert_eq!(legal.label(), "1 legal origin");
+    }
+}
@@ -1,59 +1,129 @@
+mod camera;
+mod editor;
+mod heatmap;
+mod human;
+mod inspector;
+mod layout;
+mod legal;
+mod playback;
+mod theme;
+mod timeline;
+
+pub use camera::*;
+pub use editor::*;
+pub use heatmap::*;
+pub use human::*;
+pub use inspector::*;
+pub use layout::*;
+pub use legal::*;
+pub use playback::*;
+pub use theme::*;
+pub use timeline::*;
+
+use filler::Piece;
 use ggez::graphics::{Color, DrawMode, MeshBuilder, Rect};
 
+/// The filled cells of `piece` as `cell`-sized squares from `origin`,
+/// empty ones outlined in `grid`.
+pub fn piece_mesh(
+    piece: &Piece,
+    origin: (f32, f32),
+    cell: f32,
+    color: Color,
+    grid: Color,
+) -> MeshBuilder {
+    let mut mesh_builder = MeshBuilder::new();
+    for (y, row) in piece.cells.iter().enumerate() {
+        for (x, c) in row.iter().enumerate() {
+            let rect = Rect::new(
+                origin.0 + x as f32 * cell,
+                origin.1 + y as f32 * cell,
+                cell,
+                cell,
+            );
+            let mode = if *c == '.' {
+                DrawMode::stroke(1.0)
+            } else {
+                DrawMode::fill()
+            };
+            let color = if *c == '.' { grid } else { color };
+            let _ = mesh_builder.rectangle(mode, rect, color);
+        }
+    }
+    mesh_builder
+}
+
+#[derive(Default)]
 pub struct Grid {
     pub rect: Rect,
     pub cell_size: (f32, f32),
     pub rows: usize,
     pub cols: usize,
+    pub layout: Layout,
+    pub camera: Camera,
 }
 
 impl Grid {
     pub fn new() -> Self {
-        Grid {
-            rect: Rect::default(),
-            cell_size: (0.0, 0.0),
-            rows: 0,
-            cols: 0,
-        }
+        Self::default()
     }
 
+    /// Lays out an `r` x `c` board in a window of `size`.
     pub fn init(&mut self, r: usize, c: usize, size: (f32, f32)) {
-        self.rect = Rect::new(size.0 / 6.0, 20.0, 800.0, 600.0);
-        let c_size = (
-            (self.rect.w / (c) as f32).round(),
-            (self.rect.h / (r) as f32).round(),
-        );
-        self.cell_size = c_size;
+        self.layout = Layout::compute(size, c, r);
         self.cols = c;
         self.rows = r;
+        self.apply_camera();
     }
 
-    pub fn build(&self) -> Option<MeshBuilder> {
+    /// Moves `rect` and `cell_size` to where the camera puts the board.
+    pub fn apply_camera(&mut self) {
+        self.rect = self.camera.transform(self.layout.board);
+        let cell = self.layout.cell * self.camera.zoom;
+        self.cell_size = (cell, cell);
+    }
+
+    /// Screen rectangle of a board cell.
+    pub fn cell_rect(&self, col: i32, row: i32) -> Rect {
+        Rect::new(
+            self.rect.x + col as f32 * self.cell_size.0,
+            self.rect.y + row as f32 * self.cell_size.1,
+            self.cell_size.0,
+            self.cell_size.1,
+        )
+    }
+
+    /// Board cell under a window position.
+    pub fn cell_at(&self, x: f32, y: f32) -> Option<(i32, i32)> {
+        if !self.rect.contains([x, y])
+            || !self.layout.view.contains([x, y])
+            || self.cell_size.0 <= 0.0
+        {
+            return None;
+        }
+        let col = ((x - self.rect.x) / self.cell_size.0) as i32;
+        let row = ((y - self.rect.y) / self.cell_size.1) as i32;
+        (col < self.cols as i32 && row < self.rows as i32).then_some((col, row))
+    }
+
+    pub fn build(&self, theme: &Theme) -> Option<MeshBuilder> {
         let mut mesh_builder = MeshBuilder::new();
-        let _ = mesh_builder.rectangle(DrawMode::fill(), self.rect, Color::from_rgb(45, 49, 66));
-        let w = (self.cols - 2) as f32 * self.cell_size.0;
+        let _ = mesh_builder.rectangle(DrawMode::fill(), self.rect, theme.board);
+        let w = self.cols as f32 * self.cell_size.0;
         let h = self.rows as f32 * self.cell_size.1;
 
-        for row in 0..self.rows {
+        for row in 0..=self.rows {
             let y = self.rect.y + row as f32 * self.cell_size.1;
             let start_point = [self.rect.x, y];
             let end_point = [self.rect.x + w, y];
-            let _ = mesh_builder.line(
-                &[start_point, end_point],
-                1.0,
-                Color::from_rgba(128, 128, 128, 126),
-            );
+            let _ = mesh_builder.line(&[start_point, end_point], 1.0, theme.grid);
         }
 
-        for col in 0..(self.cols - 1) {
+        for col in 0..=self.cols {
             let x = self.rect.x + col as f32 * self.cell_size.0;
             let start_point = [x, self.rect.y];
             let end_point = [x, self.rect.y + h];
-            let _ = mesh_builder.line(
-                &[start_point, end_point],
-                1.0,
-                Color::from_rgba(128, 128, 128, 126),
-            );
+            let _ = mesh_builder.line(&[start_point, end_point], 1.0, theme.grid);
         }
 
         Some(mesh_builder)
@@ -1,111 +1,676 @@
-use std::io::{self, BufRead};
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
 
-use filler::{Anfield, Robot};
-use ggez::conf::WindowMode;
+use filler::dialect::Dialect;
+use filler::protocol::{self, ExecLine, Turn, Validation};
+use filler::{Anfield, Piece, PlayerRegistry, Robot};
+use ggez::conf::{FullscreenType, WindowMode};
+use ggez::event::MouseButton;
 use ggez::event::{self, EventHandler};
 use ggez::glam::Vec2;
 use ggez::graphics::{
-    self, Canvas, Color, DrawParam, Mesh, MeshBuilder, PxScale, Text, TextFragment,
+    self, Canvas, DrawParam, Mesh, MeshBuilder, PxScale, Rect, Text, TextFragment,
 };
+use ggez::input::keyboard::{KeyCode, KeyInput, KeyMods};
 use ggez::{Context, ContextBuilder, GameResult};
-use visualizer::Grid;
+use visualizer::{
+    minimap, piece_mesh, resized, resized_piece, Editor, Frame, Grid, Heatmap, Human, Inspector,
+    LegalMoves, Playback, Theme, Timeline,
+};
+
+const USAGE: &str = "usage: visualizer [--windowed | --fullscreen] [--symbols a@,s$,...] \
+                     [--theme dark|light|colorblind|<file>] [--edit <position> | --play]";
+
+const EDITOR_HELP_HEIGHT: f32 = 110.0;
+/// Zoom factor of one mouse wheel notch.
+const ZOOM_STEP: f32 = 1.2;
+/// Pixels the mouse has to travel with a button down to pan the board.
+const DRAG_THRESHOLD: f32 = 4.0;
+
+struct Options {
+    fullscreen: bool,
+    /// Board symbols from `--symbols`, instead of the dialect's.
+    players: Option<PlayerRegistry>,
+    /// Start in edit mode, saving to this file.
+    edit: Option<PathBuf>,
+    /// Act as a player: read our turns from stdin, answer on stdout.
+    play: bool,
+    theme: Theme,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        fullscreen: true,
+        players: None,
+        edit: None,
+        play: false,
+        theme: Theme::default(),
+    };
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--windowed" => options.fullscreen = false,
+            "--fullscreen" => options.fullscreen = true,
+            "--symbols" => {
+                let v = args
+                    .next()
+                    .ok_or_else(|| format!("`--symbols` expects a value\n{USAGE}"))?;
+                options.players = Some(PlayerRegistry::parse(v)?);
+            }
+            "--edit" => {
+                let v = args
+                    .next()
+                    .ok_or_else(|| format!("`--edit` expects a value\n{USAGE}"))?;
+                options.edit = Some(PathBuf::from(v));
+            }
+            "--play" => options.play = true,
+            "--theme" => {
+                let v = args
+                    .next()
+                    .ok_or_else(|| format!("`--theme` expects a value\n{USAGE}"))?;
+                options.theme = Theme::find(v)?;
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            other => return Err(format!("unknown argument `{other}`\n{USAGE}")),
+        }
+    }
+    if options.edit.is_some() && options.play {
+        return Err(format!("`--edit` and `--play` cannot be combined\n{USAGE}"));
+    }
+    Ok(options)
+}
 
 fn main() {
-    let (mut ctx, event_loop) = ContextBuilder::new("filler_visualizer", "bcoulibal")
-        .window_mode(WindowMode {
-            fullscreen_type: ggez::conf::FullscreenType::True,
+    let options = parse_args().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(2)
+    });
+
+    let window_mode = if options.fullscreen {
+        WindowMode {
+            fullscreen_type: FullscreenType::True,
             maximized: true,
             ..Default::default()
-        })
+        }
+    } else {
+        WindowMode::default()
+            .dimensions(1280.0, 800.0)
+            .resizable(true)
+    };
+    let (mut ctx, event_loop) = ContextBuilder::new("filler_visualizer", "bcoulibal")
+        .window_mode(window_mode)
         .build()
         .expect("aieee, could not create ggez context!");
 
-    let my_game = VState::new(&mut ctx);
+    let mut my_game = VState::new(&mut ctx);
+    if let Some(players) = options.players {
+        my_game.players = players;
+        my_game.symbols_given = true;
+    }
+    my_game.theme = options.theme;
+    my_game.theme.install_font(&mut ctx);
+    match options.edit {
+        Some(path) => my_game.start_editor(path),
+        None => my_game.turns = Some(spawn_reader()),
+    }
+    if options.play {
+        my_game.human = Some(Human::default());
+    }
 
     event::run(ctx, event_loop, my_game);
 }
 
+/// Reads turns from stdin on a separate thread so the window keeps
+/// responding while the referee is quiet.
+fn spawn_reader() -> Receiver<Vec<String>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut input = io::stdin().lock();
+        while let Ok(Some(turn)) = protocol::read_turn(&mut input) {
+            if tx.send(turn).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
 struct VState {
-    pub robot1: Robot,
-    pub robot2: Robot,
+    pub players: PlayerRegistry,
+    /// Format of the referee, from the last board.
+    pub dialect: Dialect,
+    /// `players` came from `--symbols` and is kept whatever the dialect.
+    pub symbols_given: bool,
+    /// `== O fin: 42` lines of the classic referee seen so far.
+    pub finals: Vec<(i32, u32)>,
+    /// Players found on the board, by id.
+    pub robots: Vec<Robot>,
+    /// `$$$ exec` lines seen so far, naming the players.
+    pub execs: Vec<ExecLine>,
     pub anfield: Anfield,
     pub grid: Grid,
     pub started: bool,
-    pub winner: Option<u8>,
+    pub winner: Option<i32>,
+    pub piece: Piece,
+    /// Player the current piece is offered to.
+    pub to_move: i32,
+    /// Players that failed to place on their last turn.
+    pub stuck: Vec<i32>,
+    pub heatmap: Heatmap,
+    pub legal: LegalMoves,
+    pub timeline: Timeline,
+    /// Set in edit mode, where the board comes from the mouse, not stdin.
+    pub editor: Option<Editor>,
+    /// Turns read from stdin, `None` in edit mode.
+    pub turns: Option<Receiver<Vec<String>>>,
+    /// Set in play mode.
+    pub human: Option<Human>,
+    pub theme: Theme,
+    pub inspector: Inspector,
+    /// Button held on the board and how far the mouse moved since.
+    pub drag: Option<(MouseButton, f32)>,
+    pub playback: Playback,
 }
 
 impl VState {
     pub fn new(_ctx: &mut Context) -> VState {
         VState {
-            robot1: Robot::default(),
-            robot2: Robot::default(),
+            players: PlayerRegistry::default(),
+            dialect: Dialect::default(),
+            symbols_given: false,
+            finals: Vec::new(),
+            robots: Vec::new(),
+            execs: Vec::new(),
             anfield: Anfield::default(),
             grid: Grid::new(),
             started: false,
             winner: None,
+            piece: Piece::default(),
+            to_move: 0,
+            stuck: Vec::new(),
+            heatmap: Heatmap::default(),
+            legal: LegalMoves::default(),
+            timeline: Timeline::default(),
+            editor: None,
+            turns: None,
+            human: None,
+            theme: Theme::default(),
+            inspector: Inspector::default(),
+            drag: None,
+            playback: Playback::default(),
         }
     }
 
-    pub fn draw_scores(&self, canvas: &mut Canvas) {
-        let text1 = Text::new(TextFragment {
-            text: self.robot1.score.to_string(),
-            color: Some(Color::GREEN),
-            font: Some("LiberationMono-Regular".into()),
-            scale: Some(PxScale::from(30.0)),
+    /// Switches to edit mode, starting from the position in `path` if it
+    /// can be loaded.
+    pub fn start_editor(&mut self, path: PathBuf) {
+        let (anfield, piece) = match filler::snapshot::load(&path) {
+            Ok(state) => (state.anfield, state.current_piece),
+            Err(_) => Editor::blank(),
+        };
+        self.anfield = anfield;
+        self.piece = piece;
+        for id in 1..=2 {
+            self.add_robot(id);
+        }
+        self.to_move = 1;
+        self.started = true;
+        self.editor = Some(Editor::new(path));
+    }
 
-            ..Default::default()
+    fn edit_key(&mut self, key: KeyCode, mods: KeyMods) {
+        let (dw, dh) = match key {
+            KeyCode::Left => (-1, 0),
+            KeyCode::Right => (1, 0),
+            KeyCode::Up => (0, -1),
+            KeyCode::Down => (0, 1),
+            KeyCode::S => {
+                if let Some(editor) = self.editor.as_mut() {
+                    editor.save(&self.anfield, &self.piece, &self.players);
+                }
+                return;
+            }
+            _ => return,
+        };
+        if mods.contains(KeyMods::SHIFT) {
+            self.piece = resized_piece(&self.piece, self.piece.width + dw, self.piece.height + dh);
+        } else {
+            self.anfield = resized(
+                &self.anfield,
+                self.anfield.width + dw,
+                self.anfield.height + dh,
+            );
+        }
+    }
+
+    fn edit_click(&mut self, x: f32, y: f32) {
+        if let Some(coord) = self.grid.cell_at(x, y) {
+            Editor::cycle_cell(&mut self.anfield, coord);
+            return;
+        }
+        let rect = self.piece_rect();
+        let cell = self.piece_cell();
+        if cell > 0.0 && x >= rect.x && y >= rect.y {
+            let coord = (((x - rect.x) / cell) as i32, ((y - rect.y) / cell) as i32);
+            Editor::toggle_piece_cell(&mut self.piece, coord);
+        }
+    }
+
+    /// Shows the next turn from the referee and updates everything derived
+    /// from the sequence of turns.
+    fn apply_turn(&mut self, lines: Vec<String>) {
+        let before = self.scores();
+        let new_board = Dialect::detect(&lines).is_some();
+        self.parse(lines.clone());
+        for robot in self.robots.iter_mut() {
+            robot.update_score(&self.anfield);
+        }
+        if new_board {
+            self.timeline.record(self.scores());
+            self.inspector
+                .record(&self.anfield, self.timeline.history.len());
+            self.advance_turn(&before);
+            self.offer_piece();
+            self.update_overlays();
+        }
+        self.playback.frames.push(Frame {
+            lines,
+            to_move: self.to_move,
+            winner: self.winner,
         });
+    }
 
-        let text2 = Text::new(TextFragment {
-            text: self.robot2.score.to_string(),
-            color: Some(Color::YELLOW),
-            font: Some("LiberationMono-Regular".into()),
-            scale: Some(PxScale::from(30.0)),
+    /// Shows an earlier turn again.
+    fn show_frame(&mut self, frame: Frame) {
+        self.parse(frame.lines);
+        self.to_move = frame.to_move;
+        self.winner = frame.winner;
+        for robot in self.robots.iter_mut() {
+            robot.update_score(&self.anfield);
+        }
+        self.update_overlays();
+    }
 
-            ..Default::default()
+    /// Recomputes the heatmap and legal moves for the player to move.
+    fn update_overlays(&mut self) {
+        let robot = self.robot(self.to_move).cloned().unwrap_or_default();
+        self.heatmap.update(&self.anfield, &self.piece, &robot);
+        self.legal.update(&self.anfield, &self.piece, &robot);
+    }
+
+    fn playback_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Space => {
+                let was_back = self.playback.shown.is_some();
+                self.playback.toggle_pause();
+                if was_back && self.playback.shown.is_none() {
+                    if let Some(frame) = self.playback.frames.last().cloned() {
+                        self.show_frame(frame);
+                    }
+                }
+            }
+            KeyCode::Left => {
+                if let Some(frame) = self.playback.back().cloned() {
+                    self.show_frame(frame);
+                }
+            }
+            KeyCode::Right => match self.playback.forward().cloned() {
+                Some(frame) => self.show_frame(frame),
+                None => {
+                    if let Some(lines) = self.playback.take() {
+                        self.apply_turn(lines);
+                    }
+                }
+            },
+            KeyCode::Plus | KeyCode::Equals | KeyCode::NumpadAdd => self.playback.slower(),
+            KeyCode::Minus | KeyCode::NumpadSubtract => self.playback.faster(),
+            _ => {}
+        }
+    }
+
+    /// In play mode, a new board means the piece is ours to place. With
+    /// nowhere to put it, answer `0 0` straight away.
+    fn offer_piece(&mut self) {
+        let Some(human) = self.human.as_mut() else {
+            return;
+        };
+        if human.id == 0 {
+            human.id = self.execs.first().map_or(1, |e| e.player);
+        }
+        self.to_move = human.id;
+        human.awaiting = true;
+        if !human.has_legal_move(&self.anfield, &self.piece) {
+            if let Err(err) = human.submit((0, 0), self.dialect) {
+                eprintln!("could not send move: {err}");
+            }
+        }
+    }
+
+    fn play_click(&mut self, x: f32, y: f32) {
+        let Some(cell) = self.grid.cell_at(x, y) else {
+            return;
+        };
+        let Some(human) = self.human.as_mut() else {
+            return;
+        };
+        let coord = Human::origin(&self.piece, cell);
+        if human.awaiting && human.can_place(&self.anfield, &self.piece, coord) {
+            if let Err(err) = human.submit(coord, self.dialect) {
+                eprintln!("could not send move: {err}");
+            }
+        }
+    }
+
+    /// The offered piece at the cursor, tinted by whether it fits there.
+    pub fn build_cursor(&self) -> Option<MeshBuilder> {
+        let human = self.human.as_ref().filter(|h| h.awaiting)?;
+        let (col, row) = Human::origin(&self.piece, human.cursor?);
+        let legal = human.can_place(&self.anfield, &self.piece, (col, row));
+        let origin = self.grid.cell_rect(col, row);
+        Some(piece_mesh(
+            &self.piece,
+            (origin.x, origin.y),
+            self.grid.cell_size.0,
+            Human::tint(legal, &self.theme),
+            self.theme.grid,
+        ))
+    }
+
+    /// Fits the current board into a window of `size`.
+    pub fn layout_grid(&mut self, size: (f32, f32)) {
+        let (c, r) = (self.anfield.width as usize, self.anfield.height as usize);
+        self.grid.init(r, c, size);
+    }
+
+    /// Room for the score chart, below the per-player scores.
+    pub fn timeline_rect(&self) -> Rect {
+        let panel = self.grid.layout.panel;
+        let top = panel.y + self.robots.len() as f32 * 50.0 + 50.0;
+        Rect::new(panel.x, top, panel.w, (panel.w * 0.6).min(panel.h / 3.0))
+    }
+
+    /// Binary name of player `id`, or `player<id>` if it was never announced.
+    pub fn name(&self, id: i32) -> String {
+        self.execs
+            .iter()
+            .find(|e| e.player == id)
+            .map_or_else(|| format!("player{id}"), |e| e.name().to_string())
+    }
+
+    fn add_robot(&mut self, id: i32) {
+        if self.robot(id).is_none() {
+            self.robots.push(self.players.robot(id));
+            self.robots.sort_by_key(|r| r.id);
+        }
+    }
+
+    /// Where the offered piece is drawn, below the score chart.
+    pub fn piece_rect(&self) -> Rect {
+        let panel = self.grid.layout.panel;
+        let top = self.timeline_rect().bottom() + 120.0;
+        // Keep the bottom of the panel for the editor help.
+        let h = panel.bottom() - top - EDITOR_HELP_HEIGHT;
+        Rect::new(panel.x, top, panel.w, h.max(0.0))
+    }
+
+    /// Side of a cell of the offered piece in the panel.
+    fn piece_cell(&self) -> f32 {
+        let rect = self.piece_rect();
+        (rect.w / self.piece.width.max(1) as f32)
+            .min(rect.h / self.piece.height.max(1) as f32)
+            .min(self.grid.layout.cell.max(8.0))
+    }
+
+    pub fn build_piece(&self) -> Option<MeshBuilder> {
+        if self.piece.width == 0 || self.piece.height == 0 {
+            return None;
+        }
+        let rect = self.piece_rect();
+        let cell = self.piece_cell();
+        Some(piece_mesh(
+            &self.piece,
+            (rect.x, rect.y),
+            cell,
+            self.theme.player(self.to_move),
+            self.theme.grid,
+        ))
+    }
+
+    pub fn robot(&self, id: i32) -> Option<&Robot> {
+        self.robots.iter().find(|r| r.id == id)
+    }
+
+    pub fn scores(&self) -> Vec<(i32, u32)> {
+        self.robots.iter().map(|r| (r.id, r.score)).collect()
+    }
+
+    /// Next player in turn order after `id` that can still move.
+    fn next_player(&self, id: i32) -> i32 {
+        let ids: Vec<i32> = self.robots.iter().map(|r| r.id).collect();
+        let start = ids.iter().position(|&i| i == id).map_or(0, |p| p + 1);
+        (0..ids.len())
+            .map(|k| ids[(start + k) % ids.len()])
+            .find(|i| !self.stuck.contains(i))
+            .unwrap_or(id)
+    }
+
+    /// Works out whose turn it is from which player gained cells since the
+    /// previous board.
+    pub fn advance_turn(&mut self, before: &[(i32, u32)]) {
+        if self.to_move == 0 {
+            self.to_move = self.robots.first().map_or(0, |r| r.id);
+            return;
+        }
+        let gained: Vec<i32> = self
+            .robots
+            .iter()
+            .filter(|r| {
+                let old = before.iter().find(|(id, _)| *id == r.id).map_or(0, |s| s.1);
+                r.score > old
+            })
+            .map(|r| r.id)
+            .collect();
+        let last = match gained.as_slice() {
+            [id] => *id,
+            [] => {
+                if !self.stuck.contains(&self.to_move) {
+                    self.stuck.push(self.to_move);
+                }
+                self.to_move
+            }
+            _ => self.to_move,
+        };
+        self.to_move = self.next_player(last);
+    }
+
+    pub fn draw_scores(&self, canvas: &mut Canvas) {
+        let x = self.grid.layout.panel.x;
+        let mut y = self.grid.layout.panel.y;
+        for robot in &self.robots {
+            let text = Text::new(TextFragment {
+                text: format!("{} {}", self.name(robot.id), robot.score),
+                color: Some(self.theme.player(robot.id)),
+                font: Some(self.theme.font.clone()),
+                scale: Some(PxScale::from(30.0)),
+            });
+            canvas.draw(&text, Vec2::new(x, y));
+            let idle = Text::new(TextFragment {
+                text: format!(
+                    "last placed {} turns ago",
+                    self.timeline.turns_since_placed(robot.id)
+                ),
+                color: Some(self.theme.player(robot.id)),
+                font: Some(self.theme.font.clone()),
+                scale: Some(PxScale::from(16.0)),
+            });
+            canvas.draw(&idle, Vec2::new(x, y + 30.0));
+            y += 50.0;
+        }
+
+        let lead = match self.timeline.lead() {
+            Some((id, margin)) => format!("player{id} leads by {margin}"),
+            None => "tied".to_string(),
+        };
+        let lead = Text::new(TextFragment {
+            text: lead,
+            color: Some(self.theme.text),
+            font: Some(self.theme.font.clone()),
+            scale: Some(PxScale::from(20.0)),
         });
-        let x = self.grid.rect.x + self.grid.rect.w / 2.0;
-        let y = self.grid.rect.y + self.grid.rect.h + 20.0;
-        canvas.draw(&text1, Vec2::new(x, y));
-        let y = y + 20.0;
-        canvas.draw(&text2, Vec2::new(x, y));
+        canvas.draw(&lead, Vec2::new(x, y));
+        if self.editor.is_none() && self.human.is_none() {
+            let status = Text::new(TextFragment {
+                text: self.playback.status(),
+                color: Some(self.theme.text),
+                font: Some(self.theme.font.clone()),
+                scale: Some(PxScale::from(14.0)),
+            });
+            canvas.draw(&status, Vec2::new(x, y + 24.0));
+        }
+        y = self.timeline_rect().bottom() + 10.0;
+
+        if self.heatmap.enabled {
+            let label = Text::new(TextFragment {
+                text: self.heatmap.label(),
+                color: Some(self.theme.text),
+                font: Some(self.theme.font.clone()),
+                scale: Some(PxScale::from(20.0)),
+            });
+            canvas.draw(&label, Vec2::new(x, y));
+            y += 20.0;
+        }
+        if self.legal.enabled {
+            let label = Text::new(TextFragment {
+                text: self.legal.label(),
+                color: Some(self.theme.text),
+                font: Some(self.theme.font.clone()),
+                scale: Some(PxScale::from(20.0)),
+            });
+            canvas.draw(&label, Vec2::new(x, y));
+            y += 20.0;
+        }
+
+        if self.piece.height > 0 {
+            let offered = Text::new(TextFragment {
+                text: format!("piece for {}", self.name(self.to_move)),
+                color: Some(self.theme.player(self.to_move)),
+                font: Some(self.theme.font.clone()),
+                scale: Some(PxScale::from(16.0)),
+            });
+            canvas.draw(&offered, Vec2::new(x, self.piece_rect().y - 20.0));
+        }
+
+        if let Some(editor) = &self.editor {
+            let help = Text::new(TextFragment {
+                text: format!(
+                    "click: cycle owner / piece cell\narrows: board size\n\
+                     shift+arrows: piece size\ns: save\n{}",
+                    editor.status
+                ),
+                color: Some(self.theme.text),
+                font: Some(self.theme.font.clone()),
+                scale: Some(PxScale::from(16.0)),
+            });
+            let panel = self.grid.layout.panel;
+            canvas.draw(
+                &help,
+                Vec2::new(x, panel.bottom() - EDITOR_HELP_HEIGHT + 10.0),
+            );
+        }
 
         if let Some(id) = self.winner {
             let text3 = Text::new(TextFragment {
-                text: format!("player{} won!", id),
-                color: if id == 2 {
-                    Some(Color::YELLOW)
-                } else {
-                    Some(Color::GREEN)
-                },
-                font: Some("LiberationMono-Regular".into()),
+                text: format!("{} won!", self.name(id)),
+                color: Some(self.theme.player(id)),
+                font: Some(self.theme.font.clone()),
                 scale: Some(PxScale::from(30.0)),
-
-                ..Default::default()
             });
-            let y = y + 20.0;
-            let x = x - 60.0;
-            canvas.draw(&text3, Vec2::new(x, y));
+            canvas.draw(&text3, Vec2::new(x, y + 10.0));
         }
     }
 
+    /// A one-line summary next to the hovered cell and the full details of
+    /// the pinned one.
+    pub fn draw_inspector(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        let name = |id| self.name(id);
+        let at = |(x, y): (i32, i32)| {
+            let rect = self.grid.cell_rect(x, y);
+            Vec2::new(rect.right(), rect.bottom())
+        };
+        if let Some(coord) = self.inspector.pinned {
+            let rect = self.grid.cell_rect(coord.0, coord.1);
+            let outline = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::stroke(2.0),
+                rect,
+                self.theme.highlight,
+            )?;
+            canvas.draw(&outline, DrawParam::default());
+
+            let weights = self
+                .robot(self.to_move)
+                .map(|r| r.weights)
+                .unwrap_or_default();
+            let details =
+                self.inspector
+                    .details(&self.anfield, coord, self.to_move, &weights, name);
+            self.draw_tooltip(ctx, canvas, details, at(coord))?;
+        }
+        if let Some(coord) = self
+            .inspector
+            .hover
+            .filter(|c| Some(*c) != self.inspector.pinned)
+        {
+            let summary = self.inspector.summary(&self.anfield, coord, name);
+            self.draw_tooltip(ctx, canvas, summary, at(coord))?;
+        }
+        Ok(())
+    }
+
+    /// `text` on a box at `at`, moved back inside the window if needed.
+    fn draw_tooltip(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        text: String,
+        at: Vec2,
+    ) -> GameResult {
+        let text = Text::new(TextFragment {
+            text,
+            color: Some(self.theme.text),
+            font: Some(self.theme.font.clone()),
+            scale: Some(PxScale::from(16.0)),
+        });
+        let size = Vec2::from(text.measure(ctx)?) + Vec2::splat(8.0);
+        let window = ctx.gfx.size();
+        let x = at.x.min(window.0 - size.x).max(0.0);
+        let y = at.y.min(window.1 - size.y).max(0.0);
+        let rect = Rect::new(x, y, size.x, size.y);
+        let background =
+            Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), rect, self.theme.board)?;
+        canvas.draw(&background, DrawParam::default());
+        canvas.draw(&text, Vec2::new(x + 4.0, y + 4.0));
+        Ok(())
+    }
+
     pub fn fill_grid(&self) -> MeshBuilder {
         let occ = self.anfield.occupation.to_owned();
-        let cell_size = self.grid.cell_size;
 
         let mesh_builder = &mut MeshBuilder::new();
 
         occ.into_iter().for_each(|((col, row), id)| {
             if id != 0 {
-                let x = self.grid.rect.x + col as f32 * cell_size.0;
-                let y = self.grid.rect.y + row as f32 * cell_size.1;
-
-                let color = if id == 1 { Color::GREEN } else { Color::YELLOW };
+                let color = self.theme.player(id);
 
                 mesh_builder
                     .rectangle(
                         graphics::DrawMode::fill(),
-                        graphics::Rect::new(x, y, cell_size.0, cell_size.1),
+                        self.grid.cell_rect(col, row),
                         color,
                     )
                     .unwrap();
@@ -115,79 +680,44 @@ impl VState {
     }
 
     pub fn parse(&mut self, lines: Vec<String>) {
-        let mut anfield: Anfield = Anfield::new(0, 0);
-        let robot1 = if self.started {
-            self.robot1.clone()
-        } else {
-            Robot::new(1, ['a', '@'])
-        };
-        let robot2 = if self.started {
-            self.robot2.clone()
-        } else {
-            Robot::new(2, ['s', '$'])
-        };
-        let mut pieces_cells = Vec::new();
-        let mut parsing_pieces = false;
-
-        let mut parsing_anfield = false;
-        let mut anfield_strtidx: usize = 0;
-        for (idx, line) in lines.iter().enumerate() {
-            if line.starts_with("Anfield") {
-                let part = line
-                    .trim_matches(|c: char| !c.is_numeric())
-                    .split_once(' ')
-                    .expect("error while spliting");
-                let width: i32 = part.0.parse().expect("error while parsing");
-                let height: i32 = part.1.parse().expect("error while parsing");
-                anfield = Anfield::new(width, height)
-            } else if line.trim().chars().all(char::is_numeric) {
-                parsing_anfield = true;
-                anfield_strtidx = idx + 1;
-                continue;
-            } else if line.starts_with("Piece") {
-                parsing_anfield = false;
-                parsing_pieces = true;
-                continue;
-            } else if line.contains("won") {
-                if line.contains("Player1") {
-                    self.winner = Some(1);
-                } else {
-                    self.winner = Some(2);
-                }
+        if let Some(dialect) = Dialect::detect(&lines) {
+            if !self.symbols_given {
+                self.players = dialect.players();
             }
-            if parsing_anfield {
-                let l =
-                    line.trim_matches(|c: char| !c.is_ascii_punctuation() && c != 'a' && c != 's');
-                l.char_indices().for_each(|(i, c)| {
-                    if c != '.' {
-                        if self.robot1.characters.contains(&c) {
-                            anfield
-                                .occupation
-                                .insert((i as i32, (idx - anfield_strtidx) as i32), self.robot1.id);
-                        } else {
-                            anfield
-                                .occupation
-                                .insert((i as i32, (idx - anfield_strtidx) as i32), self.robot2.id);
-                        }
-                    } else {
-                        anfield
-                            .occupation
-                            .insert((i as i32, (idx - anfield_strtidx) as i32), 0);
-                    }
-                });
+            self.dialect = dialect;
+        }
+        for line in &lines {
+            if let Some(id) = winner_id(line) {
+                self.winner = Some(id);
+            } else if let Some(score) = final_score(line, &self.players) {
+                self.finals.retain(|f| f.0 != score.0);
+                self.finals.push(score);
+                self.winner = final_winner(&self.finals);
             }
-
-            if parsing_pieces {
-                let l = line.trim();
-                let cells: Vec<char> = l.chars().collect();
-                pieces_cells.push(cells)
+        }
+        let turn = match Turn::parse(&lines, &self.players, Validation::Lenient) {
+            Ok(turn) => turn,
+            Err(err) => {
+                eprintln!("skipping turn: {err}");
+                return;
             }
+        };
+        for exec in turn.execs {
+            self.add_robot(exec.player);
+            self.execs.retain(|e| e.player != exec.player);
+            self.execs.push(exec);
         }
-
-        if anfield.width != 0 {
-            self.anfield = anfield;
-            self.robot1 = robot1;
-            self.robot2 = robot2
+        if turn.anfield.width != 0 {
+            let mut owners: Vec<i32> = turn.anfield.occupation.values().copied().collect();
+            owners.sort_unstable();
+            owners.dedup();
+            for owner in owners.into_iter().filter(|&o| o != 0) {
+                self.add_robot(owner);
+            }
+            self.anfield = turn.anfield;
+        }
+        if !turn.piece.cells.is_empty() {
+            self.piece = turn.piece;
         }
         self.started = true;
     }
@@ -195,56 +725,257 @@ impl VState {
 
 impl EventHandler for VState {
     fn update(&mut self, _ctx: &mut Context) -> GameResult {
-        let mut input_lines = Vec::new();
-        let stdin = io::stdin();
-        let mut rem_line = i32::MAX;
-
-        'read_buffer: for line in stdin.lock().lines() {
-            if let Ok(l) = line {
-                if l.starts_with("Piece") {
-                    let part = l
-                        .trim_matches(|c: char| !c.is_numeric())
-                        .split_once(' ')
-                        .expect("error while spliting");
-                    let height: i32 = part.1.parse().expect("error while parsing");
-                    rem_line = height;
-                    input_lines.push(l);
-                    continue;
-                }
-                input_lines.push(l);
-            }
-            rem_line -= 1;
-            if rem_line < 1 {
-                break 'read_buffer;
+        if self.editor.is_some() {
+            self.layout_grid(_ctx.gfx.size());
+            for robot in self.robots.iter_mut() {
+                robot.update_score(&self.anfield);
             }
+            return Ok(());
         }
 
-        self.parse(input_lines.clone());
+        if let Some(rx) = &self.turns {
+            self.playback.pending.extend(rx.try_iter());
+        }
+        // A human player must see every turn as soon as it arrives.
+        let turn = if self.human.is_some() {
+            self.playback.take()
+        } else {
+            self.playback.due()
+        };
+        if let Some(lines) = turn {
+            self.apply_turn(lines);
+        }
         if self.started {
-            let c = self.anfield.width as usize | 2;
-            let r = self.anfield.height as usize | 2;
+            self.layout_grid(_ctx.gfx.size());
+        }
+        self.started = true;
 
-            let size = _ctx.gfx.size();
+        Ok(())
+    }
+
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.layout_grid((width, height));
+        Ok(())
+    }
 
-            self.grid.init(r, c, size);
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        match button {
+            MouseButton::Right => {
+                if let Some(coord) = self.grid.cell_at(x, y) {
+                    self.inspector.toggle_pin(coord);
+                }
+            }
+            MouseButton::Left if self.editor.is_some() => self.edit_click(x, y),
+            MouseButton::Left if self.human.is_some() => self.play_click(x, y),
+            // In view mode a left click pins on release, unless it turned
+            // into a drag.
+            MouseButton::Left | MouseButton::Middle => self.drag = Some((button, 0.0)),
+            _ => {}
         }
-        input_lines.clear();
-        self.started = true;
-        self.robot1.update_score(&self.anfield);
-        self.robot2.update_score(&self.anfield);
+        Ok(())
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        if let Some((MouseButton::Left, moved)) = self.drag {
+            if button == MouseButton::Left && moved < DRAG_THRESHOLD {
+                if let Some(coord) = self.grid.cell_at(x, y) {
+                    self.inspector.toggle_pin(coord);
+                }
+            }
+        }
+        if self.drag.is_some_and(|(b, _)| b == button) {
+            self.drag = None;
+        }
+        Ok(())
+    }
 
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) -> GameResult {
+        let at = Vec2::from(ctx.mouse.position());
+        if self.grid.layout.view.contains(at) {
+            self.grid
+                .camera
+                .zoom_at(ZOOM_STEP.powf(y), at, self.grid.layout.board);
+            self.grid.apply_camera();
+        }
+        Ok(())
+    }
+
+    fn mouse_motion_event(
+        &mut self,
+        _ctx: &mut Context,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) -> GameResult {
+        if let Some((_, moved)) = self.drag.as_mut() {
+            *moved += dx.abs() + dy.abs();
+            if *moved >= DRAG_THRESHOLD {
+                self.grid.camera.pan_by(Vec2::new(dx, dy));
+                self.grid.apply_camera();
+            }
+        }
+        let cell = self.grid.cell_at(x, y);
+        self.inspector.hover = cell;
+        if let Some(human) = self.human.as_mut() {
+            human.cursor = cell;
+        }
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        if input.keycode == Some(KeyCode::F) {
+            self.grid.camera.fit();
+            self.grid.apply_camera();
+            return Ok(());
+        }
+        if self.editor.is_some() {
+            if let Some(key) = input.keycode {
+                self.edit_key(key, input.mods);
+            }
+            return Ok(());
+        }
+        match input.keycode {
+            Some(KeyCode::H) => self.heatmap.toggle(),
+            Some(KeyCode::T) => self.heatmap.next_term(),
+            Some(KeyCode::L) => self.legal.toggle(),
+            Some(key) if self.human.is_none() => self.playback_key(key),
+            _ => {}
+        }
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from_rgb(41, 45, 60));
-        if let Some(g) = self.grid.build() {
+        let mut canvas = graphics::Canvas::from_frame(ctx, self.theme.background);
+        // Keep a zoomed-in board out of the panel.
+        let _ = canvas.set_scissor_rect(self.grid.layout.view);
+        if let Some(g) = self.grid.build(&self.theme) {
             let mesh_data = Mesh::from_data(ctx, g.build());
             canvas.draw(&mesh_data, DrawParam::default());
         }
         let mesh_data = Mesh::from_data(ctx, self.fill_grid().build());
         canvas.draw(&mesh_data, DrawParam::default());
+        if let Some(h) = self.heatmap.build(&self.grid) {
+            let mesh_data = Mesh::from_data(ctx, h.build());
+            canvas.draw(&mesh_data, DrawParam::default());
+        }
+        if let Some(l) = self.legal.build(&self.grid, &self.theme) {
+            let mesh_data = Mesh::from_data(ctx, l.build());
+            canvas.draw(&mesh_data, DrawParam::default());
+        }
+        if let Some(c) = self.build_cursor() {
+            let mesh_data = Mesh::from_data(ctx, c.build());
+            canvas.draw(&mesh_data, DrawParam::default());
+        }
+        canvas.set_default_scissor_rect();
+        if !self.grid.camera.is_fitted() {
+            let map = minimap(
+                &self.anfield,
+                &self.theme,
+                self.grid.layout.view,
+                self.grid.rect,
+            );
+            if let Some(m) = map {
+                let mesh_data = Mesh::from_data(ctx, m.build());
+                canvas.draw(&mesh_data, DrawParam::default());
+            }
+        }
+        if let Some(t) = self.timeline.build(self.timeline_rect(), &self.theme) {
+            let mesh_data = Mesh::from_data(ctx, t.build());
+            canvas.draw(&mesh_data, DrawParam::default());
+        }
+        if let Some(p) = self.build_piece() {
+            let mesh_data = Mesh::from_data(ctx, p.build());
+            canvas.draw(&mesh_data, DrawParam::default());
+        }
         self.draw_scores(&mut canvas);
+        self.draw_inspector(ctx, &mut canvas)?;
         canvas.finish(ctx)
     }
 }
+
+/// Player number from the referee's result line, `== Player1 won ==` or
+/// `Player1 won`.
+fn winner_id(line: &str) -> Option<i32> {
+    let mut words = line.trim_start_matches([' ', '=']).split_whitespace();
+    let id = words.next()?.strip_prefix("Player")?.parse().ok()?;
+    words.next().filter(|w| w.starts_with("won"))?;
+    Some(id)
+}
+
+/// Player and score from a classic `== O fin: 42` line.
+fn final_score(line: &str, players: &PlayerRegistry) -> Option<(i32, u32)> {
+    let rest = line.strip_prefix("==")?.trim_start();
+    let (symbol, score) = rest.split_once("fin:")?;
+    let mut symbol = symbol.trim().chars();
+    let owner = players
+        .owner(symbol.next()?)
+        .filter(|_| symbol.next().is_none())?;
+    Some((owner, score.trim().parse().ok()?))
+}
+
+/// Best final score, once every player has one; `None` on a tie.
+fn final_winner(finals: &[(i32, u32)]) -> Option<i32> {
+    let best = finals.iter().map(|f| f.1).max()?;
+    let mut leaders = finals.iter().filter(|f| f.1 == best);
+    match (finals.len(), leaders.next(), leaders.next()) {
+        (2.., Some(&(id, _)), None) => Some(id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winner_comes_from_the_result_line_only() {
+        assert_eq!(winner_id("== Player2 won =="), Some(2));
+        assert_eq!(winner_id("Player1 won!"), Some(1));
+        assert_eq!(winner_id("  == Player12 won"), Some(12));
+        for line in [
+            "$$$ exec p1 : [robots/won]",
+            "$$$ exec p2 : [robots/player1won]",
+            "== Player1 lost ==",
+            "Player won",
+            "we won 3 games",
+            "012 ....won.....",
+        ] {
+            assert_eq!(winner_id(line), None, "{line}");
+        }
+    }
+
+    #[test]
+    fn classic_final_scores_decide_the_winner() {
+        let players = Dialect::Plateau.players();
+        assert_eq!(final_score("== O fin: 42", &players), Some((1, 42)));
+        assert_eq!(final_score("==X fin:7", &players), Some((2, 7)));
+        for line in ["== Z fin: 3", "== OX fin: 3", "O fin: 3", "== O fin: x"] {
+            assert_eq!(final_score(line, &players), None, "{line}");
+        }
+
+        assert_eq!(final_winner(&[]), None);
+        // One score in: the other player has not finished yet.
+        assert_eq!(final_winner(&[(1, 42)]), None);
+        assert_eq!(final_winner(&[(1, 42), (2, 7)]), Some(1));
+        assert_eq!(final_winner(&[(1, 7), (2, 42), (3, 9)]), Some(2));
+        assert_eq!(final_winner(&[(1, 42), (2, 42)]), None);
+    }
+}
@@ -0,0 +1,195 @@
+//! Turns buffered between stdin and the screen, so a live game can be
+//! paused, stepped through and slowed down.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// A turn that was shown, kept as the referee's lines rather than a parsed
+/// board to stay small on long games.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub lines: Vec<String>,
+    pub to_move: i32,
+    pub winner: Option<i32>,
+}
+
+#[derive(Debug)]
+pub struct Playback {
+    /// Turns read but not shown yet.
+    pub pending: VecDeque<Vec<String>>,
+    /// Turns shown so far.
+    pub frames: Vec<Frame>,
+    /// Frame on screen when stepping through history, `None` when following
+    /// the newest one.
+    pub shown: Option<usize>,
+    pub paused: bool,
+    /// Time between two turns while playing.
+    pub delay: Duration,
+    last_step: Instant,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            frames: Vec::new(),
+            shown: None,
+            paused: false,
+            delay: Duration::ZERO,
+            last_step: Instant::now(),
+        }
+    }
+}
+
+impl Playback {
+    /// Next turn to apply, if playing and the delay has passed.
+    pub fn due(&mut self) -> Option<Vec<String>> {
+        if self.paused || self.shown.is_some() || self.last_step.elapsed() < self.delay {
+            return None;
+        }
+        self.take()
+    }
+
+    /// Next turn to apply, regardless of pause and delay.
+    pub fn take(&mut self) -> Option<Vec<String>> {
+        let turn = self.pending.pop_front()?;
+        self.last_step = Instant::now();
+        Some(turn)
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if !self.paused {
+            self.shown = None;
+        }
+    }
+
+    /// Pauses and moves one frame back.
+    pub fn back(&mut self) -> Option<&Frame> {
+        self.paused = true;
+        let last = self.frames.len().checked_sub(1)?;
+        let i = self.shown.unwrap_or(last).saturating_sub(1);
+        self.shown = Some(i);
+        self.frames.get(i)
+    }
+
+    /// Pauses and moves one frame forward. Returns `None` once on the
+    /// newest frame, when the caller should apply the next pending turn.
+    pub fn forward(&mut self) -> Option<&Frame> {
+        self.paused = true;
+        let i = self.shown? + 1;
+        if i + 1 >= self.frames.len() {
+            self.shown = None;
+        } else {
+            self.shown = Some(i);
+        }
+        self.frames.get(i)
+    }
+
+    pub fn slower(&mut self) {
+        self.delay = (self.delay * 2)
+            .max(Duration::from_millis(25))
+            .min(MAX_DELAY);
+    }
+
+    pub fn faster(&mut self) {
+        self.delay /= 2;
+        if self.delay < Duration::from_millis(25) {
+            self.delay = Duration::ZERO;
+        }
+    }
+
+    /// `turn 12/40, 3 buffered, paused, 200 ms`
+    pub fn status(&self) -> String {
+        let shown = self.shown.map_or(self.frames.len(), |i| i + 1);
+        let mut out = format!("turn {shown}/{}", self.frames.len());
+        if !self.pending.is_empty() {
+            out.push_str(&format!(", {} buffered", self.pending.len()));
+        }
+        if self.paused {
+            out.push_str(", paused");
+        }
+        out.push_str(&format!(", {} ms", self.delay.as_millis()));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(n: usize) -> Frame {
+        Frame {
+            lines: vec![format!("turn {n}")],
+            to_move: 1,
+            winner: None,
+        }
+    }
+
+    fn playback(frames: usize) -> Playback {
+        Playback {
+            frames: (0..frames).map(frame).collect(),
+            ..Playback::default()
+        }
+    }
+
+    fn lines(frame: Option<&Frame>) -> Option<String> {
+        frame.map(|f| f.lines[0].clone())
+    }
+
+    #[test]
+    fn back_and_forward_step_through_frames() {
+        let mut playback = playback(3);
+        assert_eq!(lines(playback.back()), Some("turn 1".to_string()));
+        assert!(playback.paused);
+        assert_eq!(playback.status(), "turn 2/3, paused, 0 ms");
+        assert_eq!(lines(playback.back()), Some("turn 0".to_string()));
+        // The first frame is as far back as it goes.
+        assert_eq!(lines(playback.back()), Some("turn 0".to_string()));
+        assert_eq!(playback.shown, Some(0));
+
+        assert_eq!(lines(playback.forward()), Some("turn 1".to_string()));
+        assert_eq!(lines(playback.forward()), Some("turn 2".to_string()));
+        assert_eq!(playback.shown, None);
+        // On the newest frame, the caller applies the next pending turn.
+        assert_eq!(lines(playback.forward()), None);
+        assert!(playback.paused);
+    }
+
+    #[test]
+    fn paused_playback_only_gives_turns_on_request() {
+        let mut playback = playback(2);
+        playback.pending.push_back(vec!["next".to_string()]);
+        playback.back();
+        assert_eq!(playback.due(), None);
+        assert_eq!(playback.status(), "turn 1/2, 1 buffered, paused, 0 ms");
+        playback.toggle_pause();
+        assert_eq!(playback.shown, None);
+        assert_eq!(playback.due(), Some(vec!["next".to_string()]));
+        assert_eq!(playback.take(), None);
+    }
+
+    #[test]
+    fn speed_doubles_and_halves_within_bounds() {
+        let mut playback = Playback::default();
+        playback.slower();
+        assert_eq!(playback.delay, Duration::from_millis(25));
+        playback.slower();
+        assert_eq!(playback.delay, Duration::from_millis(50));
+        for _ in 0..10 {
+            playback.slower();
+        }
+        assert_eq!(playback.delay, MAX_DELAY);
+        playback.faster();
+        assert_eq!(playback.delay, MAX_DELAY / 2);
+        for _ in 0..5 {
+            playback.faster();
+        }
+        assert_eq!(playback.delay, Duration::from_micros(31250));
+        // Under 25 ms is as fast as it goes.
+        playback.faster();
+        assert_eq!(playback.delay, Duration::ZERO);
+    }
+}
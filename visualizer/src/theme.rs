@@ -0,0 +1,268 @@
+//! Colours and font of the visualizer.
+//!
+//! A theme file holds `name = value` lines; lines starting with `#` are
+//! comments. `base`, if present, comes first and picks the built-in theme
+//! to start from; colours are `#rrggbb` or `#rrggbbaa`; `player1`,
+//! `player2`, ... set the player colours; `font` is the path of a TTF/OTF
+//! file, relative to the theme file.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ggez::graphics::{Color, FontData};
+use ggez::Context;
+
+/// Font bundled with ggez, used when the theme has none or it can't load.
+pub const DEFAULT_FONT: &str = "LiberationMono-Regular";
+const THEME_FONT: &str = "theme";
+
+pub const BUILTIN: [&str; 3] = ["dark", "light", "colorblind"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    pub board: Color,
+    pub grid: Color,
+    pub text: Color,
+    /// Legal placements, pinned cells.
+    pub highlight: Color,
+    /// Illegal placements.
+    pub warning: Color,
+    pub players: Vec<Color>,
+    /// Font name to draw text with, see `install_font`.
+    pub font: String,
+    pub font_path: Option<PathBuf>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            background: Color::from_rgb(41, 45, 60),
+            board: Color::from_rgb(45, 49, 66),
+            grid: Color::from_rgba(128, 128, 128, 126),
+            text: Color::WHITE,
+            highlight: Color::new(0.2, 0.9, 0.3, 0.7),
+            warning: Color::new(0.9, 0.2, 0.2, 0.7),
+            players: vec![Color::GREEN, Color::YELLOW, Color::CYAN, Color::MAGENTA],
+            font: DEFAULT_FONT.to_string(),
+            font_path: None,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            background: Color::from_rgb(236, 236, 230),
+            board: Color::from_rgb(252, 252, 248),
+            grid: Color::from_rgba(60, 60, 60, 90),
+            text: Color::from_rgb(30, 30, 30),
+            highlight: Color::from_rgba(20, 140, 60, 180),
+            warning: Color::from_rgba(200, 30, 30, 180),
+            players: vec![
+                Color::from_rgb(30, 120, 50),
+                Color::from_rgb(200, 130, 0),
+                Color::from_rgb(0, 110, 170),
+                Color::from_rgb(160, 40, 150),
+            ],
+            ..Self::dark()
+        }
+    }
+
+    /// Okabe-Ito colours, which stay apart under the common forms of colour
+    /// blindness.
+    pub fn colorblind() -> Self {
+        Self {
+            name: "colorblind".to_string(),
+            highlight: Color::from_rgba(86, 180, 233, 180),
+            warning: Color::from_rgba(213, 94, 0, 180),
+            players: vec![
+                Color::from_rgb(0, 114, 178),
+                Color::from_rgb(230, 159, 0),
+                Color::from_rgb(0, 158, 115),
+                Color::from_rgb(204, 121, 167),
+            ],
+            ..Self::dark()
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "colorblind" => Some(Self::colorblind()),
+            _ => None,
+        }
+    }
+
+    /// A built-in theme by name, otherwise a theme file.
+    pub fn find(name: &str) -> Result<Self, String> {
+        match Self::builtin(name) {
+            Some(theme) => Ok(theme),
+            None => Self::load(Path::new(name)),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("could not read theme {}: {err}", path.display()))?;
+        let mut theme = Self::parse(&text)?;
+        if let Some(font) = theme.font_path.as_mut() {
+            if font.is_relative() {
+                *font = path.parent().unwrap_or(Path::new("")).join(&*font);
+            }
+        }
+        Ok(theme)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut theme = Self::dark();
+        let mut players = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| format!("line {}: expected `name = value`", n + 1))?;
+            let color = || {
+                parse_color(value)
+                    .ok_or_else(|| format!("line {}: invalid colour `{value}`", n + 1))
+            };
+            match key {
+                "base" => {
+                    theme = Self::builtin(value)
+                        .ok_or_else(|| format!("line {}: unknown theme `{value}`", n + 1))?
+                }
+                "name" => theme.name = value.to_string(),
+                "background" => theme.background = color()?,
+                "board" => theme.board = color()?,
+                "grid" => theme.grid = color()?,
+                "text" => theme.text = color()?,
+                "highlight" => theme.highlight = color()?,
+                "warning" => theme.warning = color()?,
+                "font" => theme.font_path = Some(PathBuf::from(value)),
+                _ => match key
+                    .strip_prefix("player")
+                    .and_then(|i| i.parse::<usize>().ok())
+                {
+                    Some(i) if i > 0 => players.push((i, color()?)),
+                    _ => return Err(format!("line {}: unknown setting `{key}`", n + 1)),
+                },
+            }
+        }
+        for (i, color) in players {
+            if theme.players.len() < i {
+                theme.players.resize(i, Color::WHITE);
+            }
+            theme.players[i - 1] = color;
+        }
+        Ok(theme)
+    }
+
+    /// Colour of player `id`, wrapping around past the last one.
+    pub fn player(&self, id: i32) -> Color {
+        if self.players.is_empty() {
+            return self.text;
+        }
+        self.players[(id - 1).rem_euclid(self.players.len() as i32) as usize]
+    }
+
+    /// Registers the theme font with ggez. If there is none or it can't be
+    /// loaded, text falls back to `DEFAULT_FONT`.
+    pub fn install_font(&mut self, ctx: &mut Context) {
+        self.font = DEFAULT_FONT.to_string();
+        let Some(path) = &self.font_path else {
+            return;
+        };
+        let data = fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| FontData::from_vec(bytes).map_err(|err| err.to_string()));
+        match data {
+            Ok(data) => {
+                ctx.gfx.add_font(THEME_FONT, data);
+                self.font = THEME_FONT.to_string();
+            }
+            Err(err) => eprintln!(
+                "could not load font {}: {err}, using {DEFAULT_FONT}",
+                path.display()
+            ),
+        }
+    }
+}
+
+fn parse_color(s: &str) -> Option<Color> {
+    let hex = s.strip_prefix('#')?;
+    if !matches!(hex.len(), 6 | 8) {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    let alpha = if hex.len() == 8 { byte(6)? } else { 255 };
+    Some(Color::from_rgba(byte(0)?, byte(2)?, byte(4)?, alpha))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colours_are_six_or_eight_hex_digits() {
+        assert_eq!(parse_color("#ff8000"), Some(Color::from_rgb(255, 128, 0)));
+        assert_eq!(
+            parse_color("#0080ff40"),
+            Some(Color::from_rgba(0, 128, 255, 64))
+        );
+        for bad in ["ff8000", "#ff800", "#ff80000", "#gg8000", "#ff80é0", ""] {
+            assert_eq!(parse_color(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn theme_files_start_from_a_base() {
+        let theme = Theme::parse(
+            "# mine\n\
+             base = light\n\
+             name = mine\n\
+             grid = #00000080\n\
+             player2 = #102030\n\
+             player6 = #ffffff\n\
+             font = fonts/mono.ttf\n",
+        )
+        .unwrap();
+        let light = Theme::light();
+        assert_eq!(theme.name, "mine");
+        assert_eq!(theme.background, light.background);
+        assert_eq!(theme.grid, Color::from_rgba(0, 0, 0, 128));
+        assert_eq!(theme.players.len(), 6);
+        assert_eq!(theme.players[0], light.players[0]);
+        assert_eq!(theme.player(2), Color::from_rgb(16, 32, 48));
+        assert_eq!(theme.player(5), Color::WHITE);
+        assert_eq!(theme.player(7), theme.player(1));
+        assert_eq!(theme.font_path, Some(PathBuf::from("fonts/mono.ttf")));
+        assert_eq!(Theme::parse("").unwrap(), Theme::dark());
+    }
+
+    #[test]
+    fn theme_errors_name_the_line() {
+        for (text, err) in [
+            ("base = neon", "line 1: unknown theme `neon`"),
+            ("\ngrid = red", "line 2: invalid colour `red`"),
+            ("player0 = #000000", "line 1: unknown setting `player0`"),
+            ("glow = #000000", "line 1: unknown setting `glow`"),
+            ("grid", "line 1: expected `name = value`"),
+        ] {
+            assert_eq!(Theme::parse(text), Err(err.to_string()));
+        }
+    }
+}
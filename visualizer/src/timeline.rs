@@ -0,0 +1,112 @@
+//! Score history of the game, drawn as a line chart in the side panel.
+
+use ggez::graphics::{DrawMode, MeshBuilder, Rect};
+
+use crate::Theme;
+
+#[derive(Debug, Default)]
+pub struct Timeline {
+    /// Scores of every player, one entry per board received.
+    pub history: Vec<Vec<(i32, u32)>>,
+}
+
+impl Timeline {
+    pub fn record(&mut self, scores: Vec<(i32, u32)>) {
+        self.history.push(scores);
+    }
+
+    fn score_at(&self, turn: usize, id: i32) -> u32 {
+        self.history[turn]
+            .iter()
+            .find(|(i, _)| *i == id)
+            .map_or(0, |s| s.1)
+    }
+
+    /// Leading player and their margin over the runner-up, `None` on a tie.
+    pub fn lead(&self) -> Option<(i32, u32)> {
+        let mut scores = self.history.last()?.clone();
+        scores.sort_by_key(|s| std::cmp::Reverse(s.1));
+        match scores.as_slice() {
+            [(id, s)] => Some((*id, *s)),
+            [(id, a), (_, b), ..] if a > b => Some((*id, a - b)),
+            _ => None,
+        }
+    }
+
+    /// Boards received since `id` last gained cells.
+    pub fn turns_since_placed(&self, id: i32) -> usize {
+        let last = self.history.len().saturating_sub(1);
+        (1..self.history.len())
+            .rev()
+            .find(|&t| self.score_at(t, id) > self.score_at(t - 1, id))
+            .map_or(last, |t| last - t)
+    }
+
+    /// One line per player across `rect`, scaled to the highest score.
+    pub fn build(&self, rect: Rect, theme: &Theme) -> Option<MeshBuilder> {
+        let ids: Vec<i32> = self.history.last()?.iter().map(|s| s.0).collect();
+        if self.history.len() < 2 {
+            return None;
+        }
+        let max = self
+            .history
+            .iter()
+            .flatten()
+            .map(|s| s.1)
+            .max()
+            .unwrap_or(0)
+            .max(1) as f32;
+        let step = rect.w / (self.history.len() - 1) as f32;
+
+        let mut mesh_builder = MeshBuilder::new();
+        let _ = mesh_builder.rectangle(DrawMode::stroke(1.0), rect, theme.grid);
+        for id in ids {
+            let points: Vec<[f32; 2]> = (0..self.history.len())
+                .map(|t| {
+                    let s = self.score_at(t, id) as f32;
+                    [rect.x + t as f32 * step, rect.y + rect.h * (1.0 - s / max)]
+                })
+                .collect();
+            let _ = mesh_builder.line(&points, 2.0, theme.player(id));
+        }
+        Some(mesh_builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline(history: &[[u32; 2]]) -> Timeline {
+        Timeline {
+            history: history.iter().map(|&[a, b]| vec![(1, a), (2, b)]).collect(),
+        }
+    }
+
+    #[test]
+    fn lead_is_the_margin_over_the_runner_up() {
+        assert_eq!(Timeline::default().lead(), None);
+        assert_eq!(timeline(&[[1, 1], [3, 1]]).lead(), Some((1, 2)));
+        assert_eq!(timeline(&[[3, 1], [3, 7]]).lead(), Some((2, 4)));
+        assert_eq!(timeline(&[[3, 1], [5, 5]]).lead(), None);
+
+        let mut three = timeline(&[[4, 2]]);
+        three.history[0].push((3, 9));
+        assert_eq!(three.lead(), Some((3, 5)));
+        let mut alone = Timeline::default();
+        alone.record(vec![(2, 6)]);
+        assert_eq!(alone.lead(), Some((2, 6)));
+    }
+
+    #[test]
+    fn turns_since_placed_counts_boards_without_growth() {
+        let game = timeline(&[[1, 1], [3, 1], [3, 4], [3, 4], [3, 4]]);
+        assert_eq!(game.turns_since_placed(1), 3);
+        assert_eq!(game.turns_since_placed(2), 2);
+        // Never grew: every board since the first.
+        let stuck = timeline(&[[1, 1], [1, 2], [1, 3]]);
+        assert_eq!(stuck.turns_since_placed(1), 2);
+        assert_eq!(stuck.turns_since_placed(2), 0);
+        assert_eq!(Timeline::default().turns_since_placed(1), 0);
+    }
+}